@@ -0,0 +1,169 @@
+//! Short animations played over the board when something happens in the game. They are
+//! driven by the frame time so they run at the same speed at any frame rate, only
+//! change how the board is drawn, and can be turned off entirely.
+use crate::*;
+use board::ButtonId;
+
+/// Seconds the dice tumble before showing the faces rolled
+const DICE_TUMBLE: f32 = 0.6;
+
+/// Seconds each face is shown while tumbling
+const TUMBLE_FACE: f32 = 0.06;
+
+/// Seconds the player marker takes to slide to the next tile
+const MARKER_SLIDE: f32 = 0.25;
+
+/// Seconds the health die flashes after taking damage
+const DAMAGE_FLASH: f32 = 0.6;
+
+/// Progress through an animation of a fixed length
+#[derive(Debug, Copy, Clone)]
+pub struct Tween {
+    /// Seconds played so far
+    elapsed: f32,
+
+    /// Seconds the animation lasts
+    duration: f32,
+}
+
+impl Tween {
+    pub fn new(duration: f32) -> Self {
+        Tween { elapsed: 0., duration }
+    }
+
+    /// Advance the animation by `dt` seconds
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    /// Returns true once the animation has played through
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Returns how far along the animation is, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        self.elapsed / self.duration
+    }
+
+    /// Returns the progress eased in and out, so movement starts and stops gently
+    pub fn eased(&self) -> f32 {
+        let t = self.progress();
+        t * t * (3. - 2. * t)
+    }
+}
+
+/// Everything currently animating on the board
+pub struct Animations {
+    /// Nothing is animated while disabled
+    enabled: bool,
+
+    /// Dice that are tumbling, by clickable index
+    tumbling: Vec<(usize, Tween)>,
+
+    /// Tile the player marker is sliding from
+    marker: Option<(usize, Tween)>,
+
+    /// Flash on the health die after taking damage
+    damage: Option<Tween>,
+}
+
+impl Animations {
+    pub fn new(enabled: bool) -> Self {
+        Animations { enabled, tumbling: Vec::new(), marker: None, damage: None }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Turn animations on or off. Turning them off skips whatever is playing.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            *self = Animations::new(false);
+        }
+    }
+
+    /// Start the animations for the outcomes of a message. `from` is the tile the player
+    /// was on before the message.
+    pub fn start(&mut self, outcomes: &[Outcome], from: usize) {
+        if !self.enabled {
+            return;
+        }
+
+        for outcome in outcomes {
+            match outcome {
+                Outcome::DiceRolled(..) => {
+                    self.tumble(ButtonId::Die1);
+                    self.tumble(ButtonId::Die2);
+                }
+                Outcome::NextTileRolled(_) => self.tumble(ButtonId::NextTile),
+                Outcome::CharmUsed(1, ..) => self.tumble(ButtonId::Die1),
+                Outcome::CharmUsed(..) => self.tumble(ButtonId::Die2),
+                Outcome::Moved { location, .. } if *location != from => {
+                    self.marker = Some((from, Tween::new(MARKER_SLIDE)));
+                }
+                Outcome::Damaged { amount, .. } if *amount > 0 => {
+                    self.damage = Some(Tween::new(DAMAGE_FLASH));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Start tumbling the given die, restarting it if it already is
+    fn tumble(&mut self, id: ButtonId) {
+        let index = id as usize;
+        self.tumbling.retain(|&(tumbling, _)| tumbling != index);
+        self.tumbling.push((index, Tween::new(DICE_TUMBLE)));
+    }
+
+    /// Advance every animation by `dt` seconds, dropping the finished ones
+    pub fn update(&mut self, dt: f32) {
+        for (_, tween) in &mut self.tumbling {
+            tween.update(dt);
+        }
+        self.tumbling.retain(|(_, tween)| !tween.is_done());
+
+        if let Some((_, tween)) = &mut self.marker {
+            tween.update(dt);
+            if tween.is_done() {
+                self.marker = None;
+            }
+        }
+
+        if let Some(tween) = &mut self.damage {
+            tween.update(dt);
+            if tween.is_done() {
+                self.damage = None;
+            }
+        }
+    }
+
+    /// Returns the face to show on a tumbling die instead of the one rolled. The faces
+    /// only need to look random, so they don't touch the game's Rng.
+    pub fn tumble_face(&self, id: ButtonId) -> Option<usize> {
+        let index = id as usize;
+        self.tumbling.iter()
+            .find(|&&(tumbling, _)| tumbling == index)
+            .map(|(_, tween)| {
+                let step = (tween.elapsed / TUMBLE_FACE) as usize;
+                (step * 5 + index * 3) % 6 + 1
+            })
+    }
+
+    /// Returns the tile the player marker is sliding from, and how far along it is
+    pub fn marker(&self) -> Option<(usize, f32)> {
+        self.marker.map(|(from, tween)| (from, tween.eased()))
+    }
+
+    /// Returns how strongly the health die is flashing, from 0 to 1
+    pub fn damage_flash(&self) -> Option<f32> {
+        self.damage.map(|tween| {
+            // Blink three times, fading out
+            let blink = (tween.progress() * 6.).fract() < 0.5;
+            if blink { 1. - tween.progress() } else { 0. }
+        })
+    }
+}
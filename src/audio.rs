@@ -0,0 +1,349 @@
+//! Sound effects for what happens in the game, and a quiet music loop behind them. Every
+//! sound is synthesized at startup so there are no audio files to ship, and is played
+//! through macroquad on native and wasm alike. The sound effects and the music each have
+//! their own volume and mute, kept between runs.
+use crate::*;
+use macroquad::audio::{load_sound_from_bytes, play_sound, set_sound_volume, PlaySoundParams,
+                       Sound};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Name the settings are stored under
+const SETTINGS_NAME: &str = "audio";
+
+/// Samples per second of the synthesized sounds
+const SAMPLE_RATE: u32 = 22050;
+
+/// How much the volume changes with each press of the volume keys
+pub const VOLUME_STEP: f32 = 0.1;
+
+/// Something in the game that makes a sound
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    DiceRoll,
+    WallPlaced,
+    WallBroken,
+    BeastAttack,
+    Trap,
+    Heal,
+    ItemPickup,
+    GameOver,
+    Victory,
+}
+
+impl Sfx {
+    /// Every sound effect
+    const ALL: [Sfx; 9] = [Sfx::DiceRoll, Sfx::WallPlaced, Sfx::WallBroken,
+                           Sfx::BeastAttack, Sfx::Trap, Sfx::Heal, Sfx::ItemPickup,
+                           Sfx::GameOver, Sfx::Victory];
+
+    /// Returns the sound made by an outcome of the rules, if any
+    pub fn for_outcome(outcome: &Outcome) -> Option<Sfx> {
+        match outcome {
+            Outcome::DiceRolled(..) | Outcome::NextTileRolled(_) | Outcome::CharmUsed(..) => {
+                Some(Sfx::DiceRoll)
+            }
+            Outcome::WallsBuilt(_, walls) if !walls.is_empty() => Some(Sfx::WallPlaced),
+            Outcome::Moved { broken_wall: Some(_), .. } => Some(Sfx::WallBroken),
+            Outcome::AttackRolled { .. } => Some(Sfx::BeastAttack),
+            Outcome::Damaged { hazard: Hazard::SneakBeast | Hazard::BeastAttack, .. } => {
+                Some(Sfx::BeastAttack)
+            }
+            Outcome::Damaged { hazard: Hazard::Trap, .. } => Some(Sfx::Trap),
+            Outcome::Healed(_) => Some(Sfx::Heal),
+            Outcome::ItemFound(_) => Some(Sfx::ItemPickup),
+            Outcome::Died | Outcome::OutOfTime => Some(Sfx::GameOver),
+            Outcome::Escaped => Some(Sfx::Victory),
+            _ => None
+        }
+    }
+
+    /// Returns the tones the sound is made of, played one after another
+    fn tones(self) -> Vec<Tone> {
+        let tone = |start_hz, end_hz, seconds, noise| {
+            Tone { start_hz, end_hz, seconds, noise, volume: 1. }
+        };
+        let rest = |seconds| Tone { start_hz: 0., end_hz: 0., seconds, noise: 0., volume: 0. };
+
+        match self {
+            // Dice clattering on the table
+            Sfx::DiceRoll => vec![
+                tone(900., 600., 0.03, 0.8), rest(0.04),
+                tone(800., 500., 0.03, 0.8), rest(0.06),
+                tone(700., 500., 0.03, 0.8), rest(0.09),
+                tone(600., 400., 0.04, 0.8),
+            ],
+            Sfx::WallPlaced  => vec![tone(140., 70., 0.12, 0.4)],
+            Sfx::WallBroken  => vec![tone(220., 50., 0.4, 0.9)],
+            Sfx::BeastAttack => vec![tone(110., 80., 0.25, 0.6), tone(90., 60., 0.3, 0.6)],
+            Sfx::Trap        => vec![tone(700., 120., 0.35, 0.15)],
+            Sfx::Heal        => vec![tone(440., 440., 0.1, 0.), tone(660., 660., 0.1, 0.),
+                                     tone(880., 880., 0.2, 0.)],
+            Sfx::ItemPickup  => vec![tone(988., 988., 0.07, 0.), tone(1319., 1319., 0.15, 0.)],
+            Sfx::GameOver    => vec![tone(392., 392., 0.25, 0.), tone(330., 330., 0.25, 0.),
+                                     tone(262., 220., 0.6, 0.)],
+            Sfx::Victory     => vec![tone(523., 523., 0.12, 0.), tone(659., 659., 0.12, 0.),
+                                     tone(784., 784., 0.12, 0.), tone(1047., 1047., 0.4, 0.)],
+        }
+    }
+}
+
+/// Returns the tones of the music, a slow minor melody that loops seamlessly
+fn music_tones() -> Vec<Tone> {
+    let note = |hz, seconds| Tone { start_hz: hz, end_hz: hz, seconds, noise: 0., volume: 0.6 };
+    let rest = |seconds| Tone { start_hz: 0., end_hz: 0., seconds, noise: 0., volume: 0. };
+
+    // A, C, E, D, then down through C and B back to A
+    vec![
+        note(220., 0.6), note(262., 0.6), note(330., 0.9), rest(0.3),
+        note(294., 0.6), note(262., 0.6), note(247., 0.9), rest(0.3),
+        note(220., 0.6), note(196., 0.6), note(220., 1.2), rest(0.6),
+        note(165., 0.6), note(196., 0.6), note(247., 0.6), note(220., 1.5), rest(0.9),
+    ]
+}
+
+/// A sweep from `start_hz` to `end_hz`, mixed with some noise
+#[derive(Debug, Copy, Clone)]
+struct Tone {
+    start_hz: f32,
+    end_hz: f32,
+    seconds: f32,
+
+    /// How much of the tone is noise instead of a pure wave, from 0 to 1
+    noise: f32,
+
+    volume: f32,
+}
+
+/// Returns the tones as a mono 16-bit WAV file
+fn synthesize(tones: &[Tone]) -> Vec<u8> {
+    let mut samples: Vec<i16> = Vec::new();
+
+    // Noise only needs to sound random, so a tiny LCG will do
+    let mut noise_state: u32 = 0x1234_5678;
+    let mut noise = || {
+        noise_state = noise_state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (noise_state >> 16) as f32 / 32768. - 1.
+    };
+
+    for tone in tones {
+        let count = (tone.seconds * SAMPLE_RATE as f32) as usize;
+        let mut phase = 0f32;
+
+        for i in 0..count {
+            let t = i as f32 / count as f32;
+            let hz = tone.start_hz + (tone.end_hz - tone.start_hz) * t;
+            phase += hz / SAMPLE_RATE as f32;
+
+            let wave = (phase * std::f32::consts::PI * 2.).sin();
+            let value = wave * (1. - tone.noise) + noise() * tone.noise;
+
+            // Fade in quickly and out slowly so the tones don't click
+            let envelope = (t * 50.).min(1.) * (1. - t).min(0.3) / 0.3;
+            samples.push((value * envelope * tone.volume * 0.5 * i16::MAX as f32) as i16);
+        }
+    }
+
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());              // Format chunk size
+    wav.extend_from_slice(&1u16.to_le_bytes());               // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes());               // Mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());  // Bytes per second
+    wav.extend_from_slice(&2u16.to_le_bytes());               // Bytes per sample
+    wav.extend_from_slice(&16u16.to_le_bytes());              // Bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    wav
+}
+
+/// Volume and mute of the sound effects and of the music, kept between runs. Settings
+/// stored before the music was added load with the music defaults.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    /// From 0 to 1
+    volume: f32,
+    muted: bool,
+
+    /// From 0 to 1
+    music_volume: f32,
+    music_muted: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { volume: 0.7, muted: false, music_volume: 0.4, music_muted: false }
+    }
+}
+
+impl Settings {
+    /// Load the stored settings, falling back to the defaults
+    fn load() -> Settings {
+        let data = match storage::read(SETTINGS_NAME) {
+            Ok(Some(data)) => data,
+            Ok(None) => return Settings::default(),
+            Err(e) => {
+                info!("{}", e);
+                return Settings::default();
+            }
+        };
+
+        ron::de::from_str(&data).unwrap_or_else(|e| {
+            info!("Failed to parse audio settings: {}", e);
+            Settings::default()
+        })
+    }
+
+    fn store(&self) {
+        let result = ron::ser::to_string(self)
+            .map_err(|e| format!("Failed to serialize audio settings: {}", e))
+            .and_then(|data| storage::write(SETTINGS_NAME, &data));
+
+        if let Err(e) = result {
+            info!("{}", e);
+        }
+    }
+}
+
+/// Every loaded sound effect and the music, along with the volumes to play them at
+#[derive(Clone)]
+pub struct Audio {
+    sounds: HashMap<Sfx, Sound>,
+
+    /// Loops for as long as the game runs, silenced rather than stopped when muted
+    music: Option<Sound>,
+
+    /// Browsers only allow sound after the player interacts with the page, so the music
+    /// waits for the first click, tap or key press
+    music_started: bool,
+
+    settings: Settings,
+}
+
+impl Audio {
+    /// Synthesize and load every sound effect and the music, and load the stored settings.
+    /// The music starts with `start_music`.
+    pub async fn load() -> Audio {
+        let mut sounds = HashMap::new();
+        for &sfx in Sfx::ALL.iter() {
+            match load_sound_from_bytes(&synthesize(&sfx.tones())).await {
+                Ok(sound) => {
+                    sounds.insert(sfx, sound);
+                }
+                Err(e) => info!("Failed to load {:?} sound: {:?}", sfx, e),
+            }
+        }
+
+        let music = match load_sound_from_bytes(&synthesize(&music_tones())).await {
+            Ok(music) => Some(music),
+            Err(e) => {
+                info!("Failed to load the music: {:?}", e);
+                None
+            }
+        };
+
+        Audio { sounds, music, music_started: false, settings: Settings::load() }
+    }
+
+    /// Start looping the music at the current settings, unless it is already playing. Call
+    /// it on the first click, tap or key press.
+    pub fn start_music(&mut self) {
+        if self.music_started {
+            return;
+        }
+
+        if let Some(music) = self.music {
+            play_sound(music, PlaySoundParams { looped: true, volume: self.music_level() });
+        }
+        self.music_started = true;
+    }
+
+    /// Play the given sound effect, unless muted
+    pub fn play(&self, sfx: Sfx) {
+        if self.settings.muted || self.settings.volume <= 0. {
+            return;
+        }
+
+        if let Some(&sound) = self.sounds.get(&sfx) {
+            play_sound(sound, PlaySoundParams { looped: false, volume: self.settings.volume });
+        }
+    }
+
+    /// Play the sounds of the outcomes of a single message, each sound at most once
+    pub fn play_outcomes(&self, outcomes: &[Outcome]) {
+        let mut played = Vec::new();
+        for sfx in outcomes.iter().filter_map(Sfx::for_outcome) {
+            if !played.contains(&sfx) {
+                self.play(sfx);
+                played.push(sfx);
+            }
+        }
+    }
+
+    /// Returns the volume, from 0 to 1
+    pub fn volume(&self) -> f32 {
+        self.settings.volume
+    }
+
+    /// Returns true while every sound is muted
+    pub fn is_muted(&self) -> bool {
+        self.settings.muted
+    }
+
+    /// Change the volume by `delta`, keeping it between 0 and 1, and store it
+    pub fn change_volume(&mut self, delta: f32) {
+        self.settings.volume = (self.settings.volume + delta).clamp(0., 1.);
+        self.settings.store();
+    }
+
+    /// Mute every sound effect if they are playing, unmute them otherwise, and store it
+    pub fn toggle_mute(&mut self) {
+        self.settings.muted = !self.settings.muted;
+        self.settings.store();
+    }
+
+    /// Returns the music volume, from 0 to 1
+    pub fn music_volume(&self) -> f32 {
+        self.settings.music_volume
+    }
+
+    /// Returns true while the music is muted
+    pub fn is_music_muted(&self) -> bool {
+        self.settings.music_muted
+    }
+
+    /// Change the music volume by `delta`, keeping it between 0 and 1, and store it
+    pub fn change_music_volume(&mut self, delta: f32) {
+        self.settings.music_volume = (self.settings.music_volume + delta).clamp(0., 1.);
+        self.settings.store();
+        self.sync_music();
+    }
+
+    /// Mute the music if it is playing, unmute it otherwise, and store it
+    pub fn toggle_music_mute(&mut self) {
+        self.settings.music_muted = !self.settings.music_muted;
+        self.settings.store();
+        self.sync_music();
+    }
+
+    /// Returns the volume the music plays at, taking mute into account
+    fn music_level(&self) -> f32 {
+        if self.settings.music_muted { 0. } else { self.settings.music_volume }
+    }
+
+    /// Play the music at the current settings
+    fn sync_music(&self) {
+        if let Some(music) = self.music {
+            set_sound_volume(music, self.music_level());
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Plays many games without a window and prints how they went
+//!
+//! Usage: simulate [games] [--policy random|greedy] [--seed first_seed]
+//!                 [--tables encounters.ron] [--difficulty name]
+//!
+//! `--tables` plays with the difficulties in the given file instead of the bundled
+//! ones, so changes to the tables can be tried before shipping them. `--difficulty`
+//! picks one of them by name, defaulting to the first.
+use templeantics::policy::{GreedyPolicy, Policy, RandomPolicy};
+use templeantics::rng::Rng;
+use templeantics::{sim, tables};
+
+fn main() {
+    let mut games = 1000;
+    let mut policy_name = "random".to_string();
+    let mut seed = 0;
+    let mut tables_path = None;
+    let mut difficulty_name = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--policy" => policy_name = args.next().expect("--policy needs a name"),
+            "--seed" => {
+                seed = args.next().and_then(|s| s.parse().ok())
+                    .expect("--seed needs a number");
+            }
+            "--tables" => tables_path = Some(args.next().expect("--tables needs a file")),
+            "--difficulty" => {
+                difficulty_name = Some(args.next().expect("--difficulty needs a name"));
+            }
+            _ => games = arg.parse().expect("Number of games must be a number"),
+        }
+    }
+
+    // The policy gets its own rng so its choices don't change the game's rolls
+    let mut policy: Box<dyn Policy> = match policy_name.as_str() {
+        "random" => Box::new(RandomPolicy::new(Rng::from_seed(seed ^ 0x5eed))),
+        "greedy" => Box::new(GreedyPolicy),
+        _ => {
+            eprintln!("Unknown policy {}. Known policies: random, greedy", policy_name);
+            std::process::exit(1);
+        }
+    };
+
+    let difficulties = match &tables_path {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))
+            .and_then(|input| tables::parse_difficulties(&input)),
+        None => Ok(tables::bundled_difficulties()),
+    };
+
+    let difficulty = difficulties.and_then(|difficulties| match &difficulty_name {
+        Some(name) => tables::find_difficulty(&difficulties, name),
+        None => Ok(difficulties[0].clone()),
+    });
+
+    let difficulty = difficulty.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    println!("Policy:         {}", policy.name());
+    println!("Difficulty:     {}", difficulty.name);
+    print!("{}", sim::simulate(games, seed, &difficulty, policy.as_mut()));
+}
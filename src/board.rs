@@ -1,133 +1,175 @@
 use crate::*;
-use std::collections::HashMap;
+use std::collections::hash_map::{Entry, HashMap};
 
-/// Game board storing game state
+/// Index of the first board tile in the clickables. Tiles are laid out row by row in
+/// the grid, so grid tile `i` is clickable `TILE_OFFSET + i`.
+pub const TILE_OFFSET: usize = 36;
+
+/// Area of the board the tile grid fills as (x, y, w, h), matching the grid printed on
+/// board.png. The tiles of the map are spread evenly over it.
+const GRID_AREA: (f32, f32, f32, f32) = (0.155, 0.155, 0.6938, 0.4886);
+
+/// Index of the first box of the turn track printed on board.png in the clickables
+const TURN_TRACK_OFFSET: usize = 10;
+
+/// Number of boxes on the turn track printed on board.png
+const TURN_TRACK_BOXES: usize = 18;
+
+/// Turns the solver looks ahead for the hints unless told otherwise. By default the
+/// hints only cover the current turn: they show the health each choice is expected to
+/// lose by the end of this turn. Looking two turns ahead searches every roll of the next
+/// turn's dice after every outcome of this one, which takes from half a second to half a
+/// minute in a release build, so deeper hints have to be asked for.
+pub const DEFAULT_HINT_DEPTH: usize = 1;
+
+/// Everything the board can be asked to do: actions for the game, and commands for the
+/// board itself that the rules engine never sees
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BoardCommand {
+    /// An action for the rules engine
+    Game(BoardMessage),
+
+    /// Roll back the last action of the turn
+    Undo,
+
+    /// Reapply the last undone action
+    Redo,
+
+    /// Throw away the current game and start a new one
+    NewGame,
+
+    /// Switch to the next bundled difficulty for the next new game
+    NextDifficulty,
+
+    /// Let the selected AI policy make the next decision, by sending the messages it
+    /// chose
+    AiMove,
+
+    /// Switch to the next AI policy
+    NextPolicy,
+
+    /// Show or hide the solver's hints for assigning the dice
+    ToggleHints,
+}
+
+impl From<BoardMessage> for BoardCommand {
+    fn from(message: BoardMessage) -> Self {
+        BoardCommand::Game(message)
+    }
+}
+
+/// Game board rendering a `GameState`
 pub struct Board {
-    /// Current state of the game
-    state: BoardState,
+    /// Rules and state of the current game
+    game: GameState,
 
     /// Background image for this board
     texture: Texture2D,
 
+    /// Colors to draw the board with instead of the background image, if any
+    theme: Option<Theme>,
+
     /// Clickable regions on the board
     clickables: Vec<Button>,
 
     /// Which square is currently selected
     selected: Option<usize>,
 
-    /// Current health of the player
-    health: usize,
+    /// Images of dice
+    dice_textures: [Texture2D; 7],
 
-    /// Value of die 1
-    die1: Option<usize>,
+    /// Translation of board tile and Wall to the clickable index.
+    walls_translation: HashMap<WallKey, usize>,
 
-    /// Value of die 2
-    die2: Option<usize>,
+    /// Banner explaining choices the player can make in the current state
+    prompt_banner: Button,
 
-    /// Currently selected die
-    selected_die: Option<usize>,
+    /// Description of what the last action did, shown when there is nothing to choose
+    last_event: Option<String>,
 
-    /// Currently selected movement location and the resulting health if the location
-    /// is chosen
-    /// (Next location, next health, Wall to break)
-    selected_move: Option<(usize, usize, Option<usize>)>,
+    /// Panel summarizing the game once it is over
+    results_panel: Button,
 
-    /// Currently selected teleport location
-    selected_teleport: Option<usize>,
+    /// Button to start a new game once the current one is over
+    new_game_button: Button,
 
-    /// Current die on Next Tile
-    next_tile: Option<usize>,
+    /// Difficulty the next new game is played at
+    next_difficulty: Difficulty,
 
-    /// Current die on Encounter
-    encounter: Option<usize>,
+    /// Button to switch the difficulty of the next new game
+    difficulty_button: Button,
 
-    /// Images of dice
-    dice_textures: [Texture2D; 7],
+    /// Actions of the current turn that can be undone
+    history: History,
 
-    /// Current location of the player. Location is the ID of the clickable as shown in 
-    /// print_debug()
-    player_location: usize,
+    /// Button to undo the last action
+    undo_button: Button,
 
-    /// Current turn
-    current_turn: usize,
+    /// Button to redo the last undone action
+    redo_button: Button,
 
-    /// Currently built walls on the board
-    built_walls: Vec<usize>,
+    /// Every message handled in this game, so it can be replayed
+    replay: Replay,
 
-    /// All visited locations on the board
-    visited_locations: Vec<usize>,
+    /// Save the game and write the replay at the end of each turn. Turned off while
+    /// watching a replay so it doesn't overwrite the player's own game.
+    autosave: bool,
 
-    /// Walls on the board. This is to dedup shared walls between neighboring spaces
-    raw_walls: HashMap<String, usize>,
+    /// AI policies the player can pick from
+    policies: Vec<Box<dyn Policy>>,
 
-    /// Translation of board space and Wall to the clickable index. 
-    walls_translation: HashMap<(usize, Wall), usize>,
+    /// Index of the selected policy in `policies`
+    policy: usize,
 
-    /// Current orientation of walls to place
-    wall_orientation: usize,
+    /// Button letting the selected policy make the next decision
+    ai_button: Button,
 
-    /// Current walls selected
-    selected_walls: Vec<Wall>,
+    /// Button switching to the next policy
+    policy_button: Button,
 
-    /// Local rng
-    rng: Rng,
+    /// Whether the solver's hints are shown while assigning dice
+    show_hints: bool,
 
-    /// Does the player have the idol
-    idol: bool,
+    /// Button showing or hiding the hints
+    hints_button: Button,
 
-    /// Does the player have the elixir
-    elixir: bool,
+    /// Best choice for each die placed on the Next Tile, with the expected health lost
+    hint_panels: Vec<Button>,
 
-    /// Does the player have the machete
-    machete: bool,
+    /// Turns the solver looks ahead for the hints
+    hint_depth: usize,
 
-    /// Number of uses for the charm
-    charm: usize,
+    /// Game and depth the hints were last searched for, along with the hints, so they
+    /// are only searched again once one of them changes
+    hint_cache: Option<(GameState, usize, Vec<solver::Hint>)>,
 
-    /// Number of uses for the pickaxe
-    pickaxe: usize,
+    /// Dice tumbling, the marker sliding and damage flashing on the board
+    animations: Animations,
 
-    /// Number of uses for the shotgun
-    shotgun: usize,
-
-    /// Number of uses for the bandage
-    bandage: usize,
+    /// Sound effects played for what happens in the game, once loaded
+    audio: Option<Audio>,
 }
 
-/// Wall positions on the board
-#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
-pub enum Wall {
-    Top,
-    Right,
-    Bottom,
-    Left
-}
+impl Board {
+    /// Create a board for a new game in the bundled temple at the given difficulty
+    pub fn new(texture: Texture2D, dice_textures: [Texture2D; 7], rng: Rng,
+               difficulty: Difficulty) -> Self {
+        Board::from_game(texture, dice_textures, GameState::with_difficulty(rng, difficulty))
+    }
 
-/// The actions of a given turn
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum BoardState {
-    AssignDice,
-    DrawWalls,
-    Movement,
-    TileEffect,
-    Encounter,
-    ChooseTeleport,
-    ShortcutDrawWalls,
-    ShortcutMovement,
-    ShortcutChooseTeleport,
-    ShortcutTileEffect,
-    EndTurn
-}
+    /// Create a board rendering the given game. The tiles and walls are laid out from
+    /// the game's map.
+    pub fn from_game(texture: Texture2D, dice_textures: [Texture2D; 7],
+                     game: GameState) -> Self {
+        let (map_width, map_height) = (game.map.width, game.map.height);
 
-impl Board {
-    pub fn new(texture: Texture2D, dice_textures: [Texture2D; 7]) -> Self {
-        // Parse Clickables 
+        // Parse Clickables
         let clickables_str = include_str!("../static/clickables.txt");
         let mut clickables = Vec::new();
 
         // Create clickables
         for line in clickables_str.split("\n") {
-            if line.len() == 0 {
+            if line.is_empty() {
                 continue;
             }
 
@@ -141,23 +183,12 @@ impl Board {
 
         // Re-write the clickables for the
         // Whole board
-        let x = 0.155;
-        let y = 0.155;
-        let w = 0.6938 / 8.;
-        let h = 0.4886 / 4.;
-
-        let mut raw_walls = HashMap::new();
-
-        let starting_id = 36;
-        let mut walls_translation = HashMap::new();
-
-        for curr_y in 0..4 {
-            for curr_x in 0..8 {
-                // Get the ID of the current button. Since they are ID'd based on 
-                // their index in clickables, the current ID is the length of
-                // the clickables
-                let curr_id = clickables.len();
+        let (x, y, grid_w, grid_h) = GRID_AREA;
+        let w = grid_w / map_width as f32;
+        let h = grid_h / map_height as f32;
 
+        for curr_y in 0..map_height {
+            for curr_x in 0..map_width {
                 // Get the coordinates for the current button.
                 let curr_x = x + (curr_x as f32 * w);
                 let curr_y = y + (curr_y as f32 * h);
@@ -166,347 +197,642 @@ impl Board {
             }
         }
 
-        for curr_y in 0..4 {
-            for curr_x in 0..8 {
-                // Get the ID of the current button. 
-                let curr_id = 36 + (curr_x + (8 * curr_y));
+        // Create a clickable for every wall. Walls shared between two tiles are
+        // deduped by their normalized wall key
+        let mut walls_translation = HashMap::new();
+
+        for curr_y in 0..map_height {
+            for curr_x in 0..map_width {
+                // Get the grid index of the current tile.
+                let curr_id = curr_x + (map_width * curr_y);
 
                 let curr_x = x + (curr_x as f32 * w);
                 let curr_y = y + (curr_y as f32 * h);
 
-                // Top wall
-                let top = Button::new(curr_x, curr_y - WALL_WIDTH / 2., w, 
-                    WALL_WIDTH);
-                let key = format!("{:?}", top).to_string();
-
-                // Assume the wall has NOT been added to the `raw_walls`. If it 
-                // had already been added, then the `curr_wall` ID is updated
-                let mut curr_wall = clickables.len();
-                if raw_walls.contains_key(&key) {
-                    info!("Wall already exists! {:?}", top);
-                    curr_wall = *raw_walls.get(&key).unwrap();
-                } else {
-                    raw_walls.insert(key.clone(), curr_wall);
-                    info!("{:?}", &top);
-                    clickables.push(top);
+                let walls = vec![
+                    (Wall::Top,    Button::new(curr_x, curr_y - WALL_WIDTH / 2., w,
+                                               WALL_WIDTH)),
+                    (Wall::Bottom, Button::new(curr_x, curr_y - WALL_WIDTH / 2. + h, w,
+                                               WALL_WIDTH)),
+                    (Wall::Left,   Button::new(curr_x - WALL_WIDTH / 2., curr_y,
+                                               WALL_WIDTH, h)),
+                    (Wall::Right,  Button::new(curr_x + w - WALL_WIDTH / 2., curr_y,
+                                               WALL_WIDTH, h)),
+                ];
+
+                for (wall, button) in walls {
+                    let key = game.map.wall_key(curr_id, wall);
+
+                    // Only add the wall if the neighbor hasn't already added it
+                    if let Entry::Vacant(entry) =
+                            walls_translation.entry(key) {
+                        entry.insert(clickables.len());
+                        clickables.push(button);
+                    }
                 }
+            }
+        }
 
-                // Insert the mapping of board square ID to wall ID
-                walls_translation.insert((curr_id, Wall::Top), curr_wall);
+        // Draw the items
+        for &item in [ButtonId::Charm, ButtonId::Machete,
+                     ButtonId::Pickaxe, ButtonId::Shotgun,
+                     ButtonId::Bandage, ButtonId::Elixir, ButtonId::Idol].iter() {
+            let button = &mut clickables[item as usize];
+            button.change_background_color(Color::new(0., 0., 0., 0.,));
+        }
 
-                // Bottom wall
-                let bottom = Button::new(curr_x, curr_y - WALL_WIDTH / 2. + h, w, 
-                    WALL_WIDTH);
-                let key = format!("{:?}", bottom).to_string();
+        clickables[ButtonId::TurnNextAction as usize].change_text_color(RED);
 
-                // Assume the wall has NOT been added to the `raw_walls`. If it 
-                // had already been added, then the `curr_wall` ID is updated
-                let mut curr_wall = clickables.len();
-                if raw_walls.contains_key(&key) {
-                    info!("Wall already exists! {:?}", bottom);
-                    curr_wall = *raw_walls.get(&key).unwrap();
-                } else {
-                    raw_walls.insert(key.clone(), curr_wall);
-                    info!("{:?}", &bottom);
-                    clickables.push(bottom);
-                }
+        info!("Seed: {} rand die1 die2: {:?} {:?}", game.seed(), game.die1, game.die2);
 
-                // Insert the mapping of board square ID to wall ID
-                walls_translation.insert((curr_id, Wall::Bottom), curr_wall);
+        let mut prompt_banner = Button::new_with_text(0.155, 0.645, 0.6938, 0.03,
+            (String::new(), WHITE));
+        prompt_banner.change_background_color(BLACK);
 
-                // Left wall
-                let left = Button::new(curr_x - WALL_WIDTH / 2., curr_y, 
-                    WALL_WIDTH, h);
-                let key = format!("{:?}", left).to_string();
+        let results_panel = Button::new_with_text(0.3, 0.25, 0.4, 0.08,
+            ("Game over".to_string(), WHITE));
 
-                // assume the wall has not been added to the `raw_walls`. if it 
-                // had already been added, then the `curr_wall` id is updated
-                let mut curr_wall = clickables.len();
-                if raw_walls.contains_key(&key) {
-                    info!("Wall already exists! {:?}", left);
-                    curr_wall = *raw_walls.get(&key).unwrap();
-                } else {
-                    raw_walls.insert(key.clone(), curr_wall);
-                    info!("{:?}", &left);
-                    clickables.push(left);
-                }
+        let new_game_button = Button::new_with_text(0.4, 0.6, 0.2, 0.06,
+            ("New game".to_string(), RED));
 
-                // Insert the mapping of board square ID to wall ID
-                walls_translation.insert((curr_id, Wall::Left), curr_wall);
+        let difficulty_button = Button::new_with_text(0.4, 0.54, 0.2, 0.045,
+            (String::new(), WHITE));
 
-                // Right wall
-                let right = Button::new(curr_x + w - WALL_WIDTH / 2., curr_y, 
-                    WALL_WIDTH, h);
-                let key = format!("{:?}", right).to_string();
+        let undo_button = Button::new_with_text(0.027, 0.15, 0.055, 0.04,
+            ("Undo".to_string(), WHITE));
 
-                // assume the wall has not been added to the `raw_walls`. if it 
-                // had already been added, then the `curr_wall` id is updated
-                let mut curr_wall = clickables.len();
-                if raw_walls.contains_key(&key) {
-                    info!("Wall already exists! {:?}", right);
-                    curr_wall = *raw_walls.get(&key).unwrap();
-                } else {
-                    raw_walls.insert(key.clone(), curr_wall);
-                    info!("{:?}", &right);
-                    clickables.push(right);
-                }
+        let redo_button = Button::new_with_text(0.087, 0.15, 0.055, 0.04,
+            ("Redo".to_string(), WHITE));
 
-                // Insert the mapping of board square ID to wall ID
-                walls_translation.insert((curr_id, Wall::Right), curr_wall);
-            }
-        }
+        let ai_button = Button::new_with_text(0.027, 0.2, 0.115, 0.04,
+            ("AI move".to_string(), WHITE));
 
-        info!("60, right: {:?}", walls_translation.get(&(60, Wall::Right)));
-        info!("61, left: {:?}", walls_translation.get(&(61, Wall::Left)));
+        let policy_button = Button::new_with_text(0.027, 0.25, 0.115, 0.04,
+            (String::new(), WHITE));
 
-        // info!("clickables: {}", clickables.len());
+        let hints_button = Button::new_with_text(0.027, 0.3, 0.115, 0.04,
+            (String::new(), WHITE));
 
-        // Setup the Next action button
-        clickables[ButtonId::TurnNextAction as usize]
-            .change_text("Next action".to_string());
-        clickables[ButtonId::TurnNextAction as usize].change_text_color(RED);
+        let hint_panels = vec![
+            Button::new_with_text(0.155, 0.565, 0.6938, 0.035, (String::new(), WHITE)),
+            Button::new_with_text(0.155, 0.605, 0.6938, 0.035, (String::new(), WHITE)),
+        ];
 
-        // Draw the items
-        for &item in [ButtonId::Charm, ButtonId::Machete, 
-                     ButtonId::Pickaxe, ButtonId::Shotgun, 
-                     ButtonId::Bandage, ButtonId::Elixir, ButtonId::Idol].iter() {
-            let mut button = &mut clickables[item as usize];
-            button.change_background_color(Color::new(0., 0., 0., 0.,));
-        }
+        let policies: Vec<Box<dyn Policy>> = vec![
+            Box::new(GreedyPolicy),
+            Box::new(RandomPolicy::new(Rng::new())),
+        ];
 
+        let replay = Replay::new(&game);
+        let next_difficulty = game.difficulty.clone();
 
-        let mut rng = Rng::new();
+        let mut board = Board {
+            game,
+            texture,
+            theme: None,
+            clickables,
+            selected: None,
+            dice_textures,
+            walls_translation,
+            prompt_banner,
+            last_event: None,
+            results_panel,
+            new_game_button,
+            next_difficulty,
+            difficulty_button,
+            history: History::new(),
+            undo_button,
+            redo_button,
+            replay,
+            autosave: true,
+            policies,
+            policy: 0,
+            ai_button,
+            policy_button,
+            show_hints: false,
+            hints_button,
+            hint_panels,
+            hint_depth: DEFAULT_HINT_DEPTH,
+            hint_cache: None,
+            animations: Animations::new(true),
+            audio: None,
+        };
 
-        let die1 = Some(rng.roll_d6());
-        let die2 = Some(rng.roll_d6());
-        info!("rand die1 die2: {:?} {:?}", die1, die2);
+        board.sync_buttons();
+        board
+    }
 
-        // Roll the initial die 1
-        let mut die1_button = &mut clickables[ButtonId::Die1 as usize];
-        die1_button.texture = Some(dice_textures[die1.unwrap()]);
+    /// Returns the current game state
+    pub fn game(&self) -> &GameState {
+        &self.game
+    }
 
-        // Roll the initial die 2
-        let mut die2_button = &mut clickables[ButtonId::Die2 as usize]; 
-        die2_button.texture = Some(dice_textures[die2.unwrap()]);
+    /// Return the command for a keyboard action, following the same rules as clicking
+    pub fn handle_key(&self, action: Action) -> Option<BoardCommand> {
+        // Once the game is over, the only option is to start a new one
+        if self.game.is_over() {
+            return match action {
+                Action::NextState => Some(BoardCommand::NewGame),
+                _ => None
+            };
+        }
 
-        // Set the starting health
-        let mut health_button = &mut clickables[ButtonId::Health as usize];
-        health_button.texture = Some(dice_textures[6]);
+        match action {
+            Action::Undo => Some(BoardCommand::Undo),
+            Action::Redo => Some(BoardCommand::Redo),
+            _ => self.game_key(action).map(BoardCommand::Game),
+        }
+    }
 
-        let starting_location = 60;
+    /// Return the game action for a keyboard action in the current state
+    fn game_key(&self, action: Action) -> Option<BoardMessage> {
+        let game = &self.game;
+        let state = game.state;
+
+        match (action, state) {
+            (Action::NextState, _) => Some(BoardMessage::NextState),
+            (Action::SelectDie1, BoardState::AssignDice) => Some(BoardMessage::ChooseDie1),
+            (Action::SelectDie2, BoardState::AssignDice) => Some(BoardMessage::ChooseDie2),
+            (Action::AssignNextTile, BoardState::AssignDice) => {
+                Some(BoardMessage::ChooseNextTile)
+            }
+            (Action::AssignEncounter, BoardState::AssignDice) => {
+                Some(BoardMessage::ChooseEncounter)
+            }
+            (Action::RotateWalls, BoardState::DrawWalls | BoardState::ShortcutDrawWalls) => {
+                Some(BoardMessage::ChangeWallOrientation)
+            }
+            (Action::Move(side), BoardState::Movement | BoardState::ShortcutMovement) => {
+                game.get_neighbors().into_iter()
+                    .find(|&(wall, _)| wall == side)
+                    .map(BoardMessage::MoveToLocation)
+            }
+            (Action::CycleTeleport,
+             BoardState::ChooseTeleport | BoardState::ShortcutChooseTeleport) => {
+                // Go to the teleport after the selected one, wrapping around
+                let teleports = &game.map.teleports;
+                let next = match game.selected_teleport
+                        .and_then(|tele| teleports.iter().position(|&t| t == tele)) {
+                    Some(index) => (index + 1) % teleports.len(),
+                    None => 0
+                };
 
-        Board { 
-            state: BoardState::AssignDice,
-            texture, 
-            clickables, 
-            selected: None, 
-            health: 6,
-            die1, 
-            die2, 
-            selected_die: None,
-            selected_move: None,
-            selected_teleport: None,
-            next_tile: None, 
-            encounter: None, 
-            dice_textures,
-            player_location: starting_location,
-            current_turn: 1,
-            wall_orientation: 0,
-            selected_walls: Vec::new(),
-            rng,
-            built_walls: Vec::new(),
-            visited_locations: vec![starting_location],
-            raw_walls,
-            walls_translation,
-            idol: false,
-            elixir: false,
-            machete: false,
-            charm: 0,
-            pickaxe: 0,
-            shotgun: 0,
-            bandage: 0,
-        }
-    } 
-
-    /// Set the current wall orientation at the current location
-    pub fn insert_walls(&mut self) {
-        for &wall in &self.selected_walls {
-            info!("Setting wall: {:?} {:?}", self.player_location, wall);
-            // Get the clickable ID for the selected walls
-            let wall_key = (self.player_location, wall);
-            if let Some(wall_index) = self.walls_translation.get(&wall_key) {
-                // Insert the wall into the built walls list
-                self.built_walls.push(*wall_index);
-            } else {
-                panic!("Did not find wall: {:?}", wall_key);
+                teleports.get(next).map(|&tele| BoardMessage::ChooseTeleport(tele))
+            }
+            (Action::UseShotgun, BoardState::DefendAttack) => Some(BoardMessage::UseShotgun),
+            (Action::TogglePickaxe, BoardState::Movement | BoardState::ShortcutMovement) => {
+                Some(BoardMessage::TogglePickaxe)
             }
+            (Action::UseCharm, BoardState::AssignDice) => Some(BoardMessage::UseCharm),
+            (Action::UseBandage, _) if self.game.can_heal() => Some(BoardMessage::UseBandage),
+            (Action::UseElixir, _) if self.game.can_heal() => Some(BoardMessage::UseElixir),
+            _ => None
         }
     }
 
-    /// Sets the value and the texture of die 1
-    pub fn set_die1(&mut self, val: Option<usize>) {
-        if let Some(new_val) = val {
-            let die1_button = &mut self.clickables[ButtonId::Die1 as usize];
-            die1_button.change_texture(self.dice_textures[new_val]);
+    /// Switch to the next way of drawing the board: the background image, then each
+    /// vector theme
+    pub fn cycle_theme(&mut self) {
+        self.theme = match self.theme {
+            None => Some(Theme::temple()),
+            Some(theme) if theme.name == Theme::temple().name => Some(Theme::night()),
+            Some(_) => None,
+        };
+    }
+
+    /// Advance the animations by `dt` seconds
+    pub fn update(&mut self, dt: f32) {
+        self.animations.update(dt);
+    }
+
+    /// Turn the animations off if they are on, on otherwise
+    pub fn toggle_animations(&mut self) {
+        let enabled = !self.animations.is_enabled();
+        self.animations.set_enabled(enabled);
+    }
+
+    /// Play sound effects for what happens in the game from now on
+    pub fn set_audio(&mut self, audio: Audio) {
+        self.audio = Some(audio);
+    }
+
+    /// Start the music, once the player has clicked, tapped or pressed a key
+    pub fn start_music(&mut self) {
+        if let Some(audio) = &mut self.audio {
+            audio.start_music();
         }
-        self.die1 = val;
     }
 
-    /// Sets the value and the texture of die 2
-    pub fn set_die2(&mut self, val: Option<usize>) {
-        if let Some(new_val) = val {
-            let die2_button = &mut self.clickables[ButtonId::Die2 as usize];
-            die2_button.change_texture(self.dice_textures[new_val]);
+    /// Mute the sound effects if they are playing, unmute them otherwise
+    pub fn toggle_mute(&mut self) {
+        if let Some(audio) = &mut self.audio {
+            audio.toggle_mute();
+            let label = if audio.is_muted() { "Sound off" } else { "Sound on" };
+            self.last_event = Some(label.to_string());
+            self.sync_buttons();
         }
-        self.die2 = val;
     }
 
-    /// Sets the value and the texture of die 2
-    pub fn set_next_tile(&mut self, val: Option<usize>) {
-        if let Some(new_val) = val {
-            let button = &mut self.clickables[ButtonId::NextTile as usize];
-            button.change_texture(self.dice_textures[new_val]);
+    /// Change the volume of the sound effects by `delta`
+    pub fn change_volume(&mut self, delta: f32) {
+        if let Some(audio) = &mut self.audio {
+            audio.change_volume(delta);
+            self.last_event = Some(format!("Volume: {:.0}%", audio.volume() * 100.));
+            self.sync_buttons();
         }
-        self.next_tile = val;
     }
 
-    /// Sets the value and the texture of health
-    pub fn set_health(&mut self, val: usize) {
-        assert!(val <= 6);
-        let button = &mut self.clickables[ButtonId::Health as usize];
-        if val == 0 {
-            button.delete_texture();
-        } else {
-            button.change_texture(self.dice_textures[val]);
+    /// Mute the music if it is playing, unmute it otherwise
+    pub fn toggle_music_mute(&mut self) {
+        if let Some(audio) = &mut self.audio {
+            audio.toggle_music_mute();
+            let label = if audio.is_music_muted() { "Music off" } else { "Music on" };
+            self.last_event = Some(label.to_string());
+            self.sync_buttons();
         }
-        self.health = val;
     }
 
-    /// Sets the value and the texture of charm
-    pub fn set_charm(&mut self, val: usize) {
-        assert!(val <= 2);
-        let button = &mut self.clickables[ButtonId::Charm as usize];
-        if val == 0 {
-            button.delete_texture();
-        } else {
-            button.change_texture(self.dice_textures[val]);
+    /// Change the volume of the music by `delta`
+    pub fn change_music_volume(&mut self, delta: f32) {
+        if let Some(audio) = &mut self.audio {
+            audio.change_music_volume(delta);
+            self.last_event = Some(format!("Music volume: {:.0}%",
+                                           audio.music_volume() * 100.));
+            self.sync_buttons();
         }
-        self.charm = val;
     }
 
-    /// Enable the machete
-    pub fn find_machete(&mut self) {
-        let button = &mut self.clickables[ButtonId::Machete as usize];
-        button.change_texture(self.dice_textures[6]);
-        self.machete = true;
+    /// Turn saving the game and writing the replay on or off
+    pub fn set_autosave(&mut self, autosave: bool) {
+        self.autosave = autosave;
     }
 
-    /// Enable the idol
-    pub fn find_idol(&mut self) {
-        let button = &mut self.clickables[ButtonId::Idol as usize];
-        button.change_texture(self.dice_textures[6]);
-        self.idol = true;
+    /// Set the number of turns the hints look ahead, at least one
+    pub fn set_hint_depth(&mut self, depth: usize) {
+        self.hint_depth = depth.max(1);
+        self.sync_hints();
     }
 
-    /// Set the value and texture for pickaxe 
-    pub fn set_pickaxe(&mut self, val: usize) {
-        assert!(val <= 2);
-        let button = &mut self.clickables[ButtonId::Pickaxe as usize];
-        if val == 0 {
-            button.delete_texture();
-        } else {
-            button.change_texture(self.dice_textures[val]);
+    /// Apply a message to the game and the board, returning what happened in the game.
+    /// Messages that change the game are recorded for undo and in the replay.
+    fn apply(&mut self, message: BoardMessage) -> Vec<Outcome> {
+        if let BoardMessage::Select(x) = message {
+            self.selected = Some(x);
         }
-        self.pickaxe = val;
+
+        let before = self.game.clone();
+        let outcomes = self.game.handle_message(message);
+        if before != self.game {
+            self.replay.record(message, before.rng().clone(), &self.game);
+        }
+        self.history.record(before, &self.game);
+        outcomes
     }
 
-    /// Set the value and texture for shotgun 
-    pub fn set_shotgun(&mut self, val: usize) {
-        assert!(val <= 2);
-        let button = &mut self.clickables[ButtonId::Shotgun as usize];
-        if val == 0 {
-            button.delete_texture();
+    /// Undo the last action of the turn, along with its step in the replay
+    fn undo(&mut self) {
+        if self.history.undo(&mut self.game) {
+            self.replay.undo();
+            self.last_event = Some("Undid the last action".to_string());
+        }
+    }
+
+    /// Redo the last undone action, along with its step in the replay
+    fn redo(&mut self) {
+        if self.history.redo(&mut self.game) {
+            self.replay.redo();
+            self.last_event = Some("Redid the last action".to_string());
+        }
+    }
+
+    /// Save the game so it can be resumed, and write the replay so far
+    fn autosave(&self, outcomes: &[Outcome]) {
+        if !self.autosave {
+            return;
+        }
+
+        // Save at the end of every turn so the game can be resumed later. A finished
+        // game has nothing left to resume.
+        let result = if self.game.is_over() {
+            save::clear().and_then(|_| self.replay.write())
+        } else if outcomes.contains(&Outcome::StateChanged(BoardState::EndTurn)) {
+            save::store(&self.game).and_then(|_| self.replay.write())
         } else {
-            button.change_texture(self.dice_textures[val]);
+            Ok(())
+        };
+
+        if let Err(e) = result {
+            info!("{}", e);
+        }
+    }
+
+    /// Returns the boxes of the turn track, which is laid out from the map when the
+    /// board is drawn without the background image
+    fn turn_boxes(&self) -> Vec<Button> {
+        let game = &self.game;
+        if self.theme.is_some() {
+            return vector::turn_boxes(game.map.turns, &game.difficulty);
+        }
+
+        let turns = game.map.turns.min(TURN_TRACK_BOXES);
+        self.clickables[TURN_TRACK_OFFSET..TURN_TRACK_OFFSET + turns].iter()
+            .map(|turn_box| Button::new(turn_box.x, turn_box.y, turn_box.w, turn_box.h))
+            .collect()
+    }
+
+    /// Draw a die, or a random face on it while it is tumbling
+    fn draw_die(&self, id: ButtonId) {
+        let button = &self.clickables[id as usize];
+        let face = match self.animations.tumble_face(id) {
+            Some(face) => face,
+            None => {
+                button.draw();
+                return;
+            }
+        };
+
+        let (x, y, w, h) = button.to_screen();
+        draw_rectangle(x, y, w, h, BLACK);
+        draw_texture_ex(self.dice_textures[face], x, y, WHITE, DrawTextureParams {
+            dest_size: Some(vec2(w, h)),
+            ..Default::default()
+        });
+    }
+
+    /// Returns the clickable for the given grid tile
+    fn tile_button(&self, tile: usize) -> &Button {
+        &self.clickables[TILE_OFFSET + tile]
+    }
+
+    /// Sets the texture of a die button. Dice with no value keep their old texture
+    /// since they are not drawn.
+    fn set_die_texture(&mut self, id: ButtonId, val: Option<usize>) {
+        if let Some(val) = val {
+            let texture = self.dice_textures[val];
+            self.clickables[id as usize].change_texture(texture);
         }
-        self.shotgun = val;
     }
 
-    /// Set the value and texture for shotgun 
-    pub fn set_bandage(&mut self, val: usize) {
-        assert!(val <= 2);
-        let button = &mut self.clickables[ButtonId::Bandage as usize];
+    /// Sets the texture of a counter button, such as health or item uses. A value of
+    /// 0 removes the texture.
+    fn set_counter_texture(&mut self, id: ButtonId, val: usize) {
         if val == 0 {
-            button.delete_texture();
+            self.clickables[id as usize].delete_texture();
         } else {
-            button.change_texture(self.dice_textures[val]);
+            let texture = self.dice_textures[val];
+            self.clickables[id as usize].change_texture(texture);
         }
-        self.bandage = val;
     }
 
-    /// Set the value and texture for elixir 
-    pub fn find_elixir(&mut self) {
-        let button = &mut self.clickables[ButtonId::Elixir as usize];
-        button.change_texture(self.dice_textures[1]);
-        self.elixir = true;
-    }
+    /// Update the textures and text of every button from the current game state
+    fn sync_buttons(&mut self) {
+        let game = &self.game;
+        let (die1, die2) = (game.die1, game.die2);
+        let (next_tile, encounter) = (game.next_tile, game.encounter);
+        let health = game.health;
+        let (charm, pickaxe, shotgun, bandage) =
+            (game.charm, game.pickaxe, game.shotgun, game.bandage);
+        let machete = if game.machete { 6 } else { 0 };
+        let idol    = if game.idol    { 6 } else { 0 };
+        let elixir  = if game.elixir  { 1 } else { 0 };
+        let state = game.state;
+
+        self.set_die_texture(ButtonId::Die1, die1);
+        self.set_die_texture(ButtonId::Die2, die2);
+        self.set_die_texture(ButtonId::NextTile, next_tile);
+        self.set_die_texture(ButtonId::Encounter, encounter);
+        self.set_counter_texture(ButtonId::Health, health);
+        self.set_counter_texture(ButtonId::Charm, charm);
+        self.set_counter_texture(ButtonId::Machete, machete);
+        self.set_counter_texture(ButtonId::Pickaxe, pickaxe);
+        self.set_counter_texture(ButtonId::Shotgun, shotgun);
+        self.set_counter_texture(ButtonId::Bandage, bandage);
+        self.set_counter_texture(ButtonId::Elixir, elixir);
+        self.set_counter_texture(ButtonId::Idol, idol);
+
+        let next_action = match state {
+            BoardState::AssignDice             => "Assign dice",
+            BoardState::DrawWalls              => "Draw walls",
+            BoardState::Movement               => "Move player",
+            BoardState::TileEffect             => "Check tile effect",
+            BoardState::Encounter              => "Check encounter",
+            BoardState::ChooseTeleport         => "Choose teleport",
+            BoardState::ShortcutDrawWalls      => "Draw shortcut walls",
+            BoardState::ShortcutMovement       => "Shortcut move",
+            BoardState::ShortcutChooseTeleport => "Choose shortcut tele",
+            BoardState::ShortcutTileEffect     => "End turn",
+            BoardState::EndTurn                => "End turn",
+            BoardState::DefendAttack           => "Take the hit",
+            BoardState::GameOver               => "Game over",
+            BoardState::Victory                => "Victory!",
+        };
 
-    /// Set the value and texture for elixir 
-    pub fn use_elixir(&mut self) {
-        let button = &mut self.clickables[ButtonId::Elixir as usize];
-        button.delete_texture();
-        self.elixir = false;
-        self.set_health(self.health + 4);
+        self.clickables[ButtonId::TurnNextAction as usize]
+            .change_text(next_action.to_string());
+
+        let title = match (state, health) {
+            (BoardState::Victory, _) => "You escaped!",
+            (_, 0)                   => "You died",
+            _                        => "Out of time",
+        };
+        self.results_panel.change_text(title.to_string());
+        self.difficulty_button.change_text(format!("Difficulty: {}",
+                                                   self.next_difficulty.name));
+
+        let prompt = self.prompt().or_else(|| self.last_event.clone());
+        self.prompt_banner.change_text(prompt.unwrap_or_default());
+
+        let policy = format!("AI: {}", self.policies[self.policy].name());
+        self.policy_button.change_text(policy);
+
+        self.sync_hints();
     }
 
-    /// Returns the neighbors of the current location
-    pub fn get_neighbors(&self) -> Vec<(Wall, usize)> {
-        let loc = self.player_location;
-        let mut neighbors = Vec::new();
-        // Locations that don't have left neighbors
-        if loc - 1 >= 36 && ![36, 44, 52, 60].contains(&loc) { 
-            neighbors.push((Wall::Left, loc - 1)); 
+    /// Recompute the solver's hints for the current dice
+    fn sync_hints(&mut self) {
+        let label = if self.show_hints { "Hints: on" } else { "Hints: off" };
+        self.hints_button.change_text(label.to_string());
+
+        let hints = if self.show_hints { self.hints() } else { Vec::new() };
+        let lost = match self.hint_depth {
+            1 => "this turn".to_string(),
+            depth => format!("over {} turns", depth),
+        };
+
+        let best_loss = hints.iter()
+            .map(|hint| hint.expected_loss)
+            .fold(f64::INFINITY, f64::min);
+
+        // One line per die, listing the loss of every wall orientation with it
+        for (i, panel) in self.hint_panels.iter_mut().enumerate() {
+            let next_tile_die = i + 1;
+            let choices = hints.iter()
+                .filter(|hint| hint.next_tile_die == next_tile_die)
+                .collect::<Vec<_>>();
+
+            if choices.is_empty() {
+                panel.change_text(String::new());
+                continue;
+            }
+
+            let losses = choices.iter()
+                .map(|hint| {
+                    let walls = match hint.orientation {
+                        Some(0) => "as drawn".to_string(),
+                        Some(turns) => format!("rotated {}x", turns),
+                        None => "rerolled".to_string(),
+                    };
+                    format!("{}: {:.2}", walls, hint.expected_loss)
+                })
+                .collect::<Vec<_>>();
+
+            panel.change_text(format!("Die {} on Next Tile, health lost {} - {}",
+                                      next_tile_die, lost, losses.join(", ")));
+
+            let best = choices.iter().any(|hint| hint.expected_loss <= best_loss);
+            panel.change_text_color(if best { GREEN } else { WHITE });
         }
+    }
 
-        // Locations that don't have right neighbors
-        if loc + 1 <= 67 && ![43, 51, 59, 67].contains(&loc) {
-            neighbors.push((Wall::Right, loc + 1)); 
+    /// Returns the solver's hints for the current game, searching again only if the game
+    /// or the depth changed since the last search
+    fn hints(&mut self) -> Vec<solver::Hint> {
+        if let Some((game, depth, hints)) = &self.hint_cache {
+            if *game == self.game && *depth == self.hint_depth {
+                return hints.clone();
+            }
         }
 
-        // Check for top neighbors
-        if loc - 8 >= 36 { 
-            neighbors.push((Wall::Top, loc - 8)); 
+        let hints = solver::hints(&self.game, self.hint_depth);
+        self.hint_cache = Some((self.game.clone(), self.hint_depth, hints.clone()));
+        hints
+    }
+
+    /// Returns the text explaining the choices available in the current state, if any
+    fn prompt(&self) -> Option<String> {
+        let game = &self.game;
+        match game.state {
+            BoardState::DefendAttack => {
+                let (hazard, roll, amount) = game.pending_attack?;
+                let machete = if game.machete { " after the machete" } else { "" };
+                Some(format!("{:?} rolled {}: {} damage{}. Click the shotgun ({} left) \
+                              to absorb {}, or take the hit",
+                             hazard, roll, amount, machete, game.shotgun, SHOTGUN_ATTACK))
+            }
+            BoardState::AssignDice if game.charm > 0 => {
+                match game.selected_die {
+                    Some(die) => Some(format!("Click the charm ({} left) to reroll die {}",
+                                              game.charm, die)),
+                    None => Some(format!("Select a die and click the charm ({} left) to \
+                                          reroll it", game.charm)),
+                }
+            }
+            BoardState::Movement | BoardState::ShortcutMovement => {
+                let selected = game.selected_move?;
+                selected.wall?;
+
+                if selected.pickaxe {
+                    Some(format!("Breaking the wall with the pickaxe ({} left). Click \
+                                  the pickaxe to pay {} health instead",
+                                 game.pickaxe, WALL_BREAK_COST))
+                } else if game.pickaxe > 0 {
+                    Some(format!("Breaking the wall costs {} health. Click the \
+                                  pickaxe ({} left) to break it for free",
+                                 WALL_BREAK_COST, game.pickaxe))
+                } else {
+                    Some(format!("Breaking the wall costs {} health", WALL_BREAK_COST))
+                }
+            }
+            _ => None
         }
+    }
 
-        // Check for top neighbors
-        if loc + 8 <= 67 {
-            neighbors.push((Wall::Bottom, loc + 8)); 
+    /// Draw the summary of a finished game along with the New game button
+    fn draw_results(&self) {
+        let game = &self.game;
+
+        // Darken the board behind the results
+        draw_rectangle(0., 0., screen_width(), screen_height(),
+                       Color::new(0., 0., 0., 0.6));
+
+        let (x, y, w, _h) = self.results_panel.to_screen();
+        let (_, bottom, _, _) = self.new_game_button.to_screen();
+        draw_rectangle(x, y, w, bottom - y, BLACK);
+        self.results_panel.draw();
+
+        let items = game.items_held().iter()
+            .map(|item| format!("{:?}", item))
+            .collect::<Vec<_>>();
+        let items = if items.is_empty() { "None".to_string() } else { items.join(", ") };
+
+        let lines = [
+            format!("Turn reached: {} / {}", game.current_turn, game.map.turns),
+            format!("Items held: {}", items),
+            format!("Tiles visited: {}", game.tiles_visited()),
+            format!("Idol: {}", if game.idol { "Found" } else { "Not found" }),
+        ];
+
+        let (_, _, _, board_h) = layout::viewport();
+        let line_height = board_h * 0.05;
+        for (i, line) in lines.iter().enumerate() {
+            let line_y = y + board_h * 0.12 + i as f32 * line_height;
+            text::draw(line, x + 10., line_y, line_height * 0.8, WHITE);
         }
-        neighbors
+
+        self.difficulty_button.draw();
+        self.new_game_button.draw();
     }
+}
 
-    /// Check if the given wall is built for the current player location
-    pub fn is_wall_built(&self, wall: Wall) -> bool {
-        let checked_wall = (self.player_location, wall);
-        let curr_wall = self.walls_translation.get(&checked_wall)
-            .expect(&format!("Wall not in translation: {:?}", checked_wall));
+/// Returns the walls drawn by a Next Tile face
+fn next_tile_effect(face: usize) -> &'static str {
+    match face {
+        1 => "3 walls, one way out",
+        2 => "2 walls in a corner",
+        3 => "2 walls on opposite sides",
+        4 => "1 wall",
+        5 => "no walls",
+        _ => "rerolled until it isn't a 6",
+    }
+}
 
-        self.built_walls.contains(&curr_wall)
+/// Returns what an Encounter face does, with the damage it can deal this turn
+fn encounter_effect(game: &GameState, face: usize) -> String {
+    let difficulty = &game.difficulty;
+    let turn = game.current_turn;
+
+    match face {
+        1 => {
+            let (low, high) = difficulty.attack_range(&difficulty.sneak_beast, turn);
+            format!("sneak beast attack, {}-{} damage", low, high)
+        }
+        2 => format!("campfire, heal {}", difficulty.campfire_heal),
+        3 => {
+            let (low, high) = difficulty.attack_range(&difficulty.beast_attack, turn);
+            format!("beast attack, {}-{} damage", low, high)
+        }
+        4 => "shortcut, draw a new Next Tile and move again".to_string(),
+        5 => "find an item, unless your tile had one".to_string(),
+        _ => format!("trap, {} damage", difficulty.trap_damage(turn)),
     }
 }
 
-/// Messages that are triggered by mouse clicks
-#[derive(Debug, Copy, Clone)]
-pub enum BoardMessage {
-    NextState,
-    Select(usize),
-    ChooseDie1,
-    ChooseDie2,
-    ChooseNextTile,
-    ChooseEncounter,
-    ChangeWallOrientation,
-    MoveToLocation((Wall, usize)),
-    ChooseTeleport(usize)
+/// Returns a short sentence describing an outcome the player should be told about
+fn describe_outcome(outcome: &Outcome) -> Option<String> {
+    match outcome {
+        Outcome::Damaged { hazard, amount, .. } =>
+            Some(format!("{:?}: -{} health", hazard, amount)),
+        Outcome::Healed(amount) => Some(format!("+{} health", amount)),
+        Outcome::ItemFound(item) => Some(format!("Found the {:?}", item)),
+        Outcome::MacheteUsed { absorbed } =>
+            Some(format!("The machete absorbed {} damage", absorbed)),
+        Outcome::ShotgunFired { absorbed } =>
+            Some(format!("The shotgun absorbed {} damage", absorbed)),
+        Outcome::CharmUsed(die, old, new) =>
+            Some(format!("The charm rerolled die {} from {} to {}", die, old, new)),
+        Outcome::PickaxeUsed(_) => Some("The pickaxe broke the wall".to_string()),
+        _ => None
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -532,34 +858,39 @@ pub enum ButtonId {
     Idol = 7,
 }
 
-impl Clickable for Board { 
-    type Message = BoardMessage;
-
-    fn handle_click(&mut self, pos: (f32, f32)) -> Option<Self::Message> {
-        info!("Handle click: {:?}", pos);
-
-        /*
-        for (i, rect) in self.clickables.iter().enumerate() {
-            if rect.contains(pos) {
-                self.selected = Some(i);
-            }
-        }
-        */
-
+impl Board {
+    /// Return the game action for a click in the current state
+    fn game_click(&self, pos: (f32, f32)) -> Option<BoardMessage> {
         // Check if we clicked Next Action
-        // info!("{:?} {:?}", pos, self.clickables[ButtonId::TurnNextAction as usize]
-            // .to_screen());
         if self.clickables[ButtonId::TurnNextAction as usize].contains(pos) {
             info!("Sending NextState");
             return Some(BoardMessage::NextState);
         }
 
+        let state = self.game.state;
+
+        // Healing items can be used until the encounter is rolled
+        if self.game.can_heal() {
+            if self.clickables[ButtonId::Bandage as usize].contains(pos) {
+                return Some(BoardMessage::UseBandage);
+            }
+
+            if self.clickables[ButtonId::Elixir as usize].contains(pos) {
+                return Some(BoardMessage::UseElixir);
+            }
+        }
+
         // Assign Dice clickable checks
-        if matches!(self.state, BoardState::AssignDice) {
-            // For assign dice, the only available options are to click one of the 
-            // 2 dice, and then click the next tile or encounter spaces
+        if matches!(state, BoardState::AssignDice) {
+            // For assign dice, the only available options are to click one of the
+            // 2 dice, and then click the next tile or encounter spaces. The charm can
+            // also reroll the selected die.
             let mut msg = None;
 
+            if self.clickables[ButtonId::Charm as usize].contains(pos) {
+                msg = Some(BoardMessage::UseCharm);
+            }
+
             if self.clickables[ButtonId::Die1 as usize].contains(pos) {
                 msg = Some(BoardMessage::ChooseDie1);
             }
@@ -577,36 +908,44 @@ impl Clickable for Board {
             }
 
             if msg.is_some() {
-                info!("{:?}: Sending msg: {:?}", self.state, msg);
+                info!("{:?}: Sending msg: {:?}", state, msg);
                 return msg;
             }
         }
 
-        if matches!(self.state, BoardState::DrawWalls) ||
-                matches!(self.state, BoardState::ShortcutDrawWalls) {
-            info!("[{:?}] Clicked on change wall orientation", self.state);
-            // For draw walls, the only available clickable location is the current 
+        if matches!(state, BoardState::DrawWalls) ||
+                matches!(state, BoardState::ShortcutDrawWalls) {
+            info!("[{:?}] Clicked on change wall orientation", state);
+            // For draw walls, the only available clickable location is the current
             // location to switch the orientation of the walls
-            if self.clickables[self.player_location].contains(pos) {
+            if self.tile_button(self.game.player_location).contains(pos) {
                 return Some(BoardMessage::ChangeWallOrientation);
             }
         }
 
-        if matches!(self.state, BoardState::Movement) ||
-                matches!(self.state, BoardState::ShortcutMovement) {
+        if matches!(state, BoardState::Movement) ||
+                matches!(state, BoardState::ShortcutMovement) {
             // For movement, only accept a click in one of the neighbors
-            for (wall, index) in self.get_neighbors() {
-                if self.clickables[index].contains(pos) {
+            for (wall, index) in self.game.get_neighbors() {
+                if self.tile_button(index).contains(pos) {
                     return Some(BoardMessage::MoveToLocation((wall, index)));
                 }
             }
+
+            // Choose between the pickaxe or health to break the wall
+            if self.clickables[ButtonId::Pickaxe as usize].contains(pos) {
+                return Some(BoardMessage::TogglePickaxe);
+            }
         }
 
-        if matches!(self.state, BoardState::ChooseTeleport) {
-            let tele_locations = [36, 43];
+        if matches!(state, BoardState::DefendAttack)
+                && self.clickables[ButtonId::Shotgun as usize].contains(pos) {
+            return Some(BoardMessage::UseShotgun);
+        }
 
-            for &index in &tele_locations {
-                if self.clickables[index].contains(pos) {
+        if matches!(state, BoardState::ChooseTeleport | BoardState::ShortcutChooseTeleport) {
+            for &index in &self.game.map.teleports {
+                if self.tile_button(index).contains(pos) {
                     return Some(BoardMessage::ChooseTeleport(index));
                 }
             }
@@ -614,593 +953,270 @@ impl Clickable for Board {
 
         None
     }
+}
 
-    fn handle_message(&mut self, message: Self::Message) { 
-        info!("Handling message: {:?}", message);
-        match message {
-            BoardMessage::NextState => {
-                match self.state {
-                    BoardState::AssignDice => {
-                        if self.die1.is_none() && self.die2.is_none() {
-                            info!("NextState -> AssignDice");
-                            self.clickables[ButtonId::TurnNextAction as usize]
-                                .change_text("Draw walls".to_string());
-                            self.state = BoardState::DrawWalls;
-
-                            // Rerolling 6 on Next Tile
-                            loop {
-                                if let Some(val) = self.next_tile {
-                                    if val != 6 { 
-                                        // Found a non-6 for the next tile, success!
-                                        self.set_next_tile(Some(val));
-                                        break; 
-                                    }
-
-
-                                    let val = self.rng.roll_d6();
-                                    // A 6 causes a reroll until not a 6 is found
-                                    self.set_next_tile(Some(val));
-                                }
-                            }
-
-
-                            // Init the walls
-                            self.wall_orientation = 0;
-                            match self.next_tile {
-                                Some(1) => [Wall::Left, Wall::Top, Wall::Right]
-                                    .iter()
-                                    .for_each(|x| self.selected_walls.push(*x)),
-                                Some(2) => [Wall::Left, Wall::Bottom].iter()
-                                    .for_each(|x| self.selected_walls.push(*x)),
-                                Some(3) => [Wall::Left, Wall::Right].iter()
-                                    .for_each(|x| self.selected_walls.push(*x)),
-                                Some(4) => [Wall::Left].iter()
-                                    .for_each(|x| self.selected_walls.push(*x)),
-                                Some(5) => {},
-                                _ => unreachable!()
-                            }
-                        }
-                    }
-                    BoardState::DrawWalls => {
-                        info!("DrawWalls -> Movement");
-                        self.clickables[ButtonId::TurnNextAction as usize]
-                            .change_text("Move player".to_string());
-                        self.state = BoardState::Movement;
-                        self.insert_walls();
-
-                        // Clear the selected walls
-                        self.selected_walls.clear();
-                    }
-                    BoardState::Movement => {
-                        if let Some((next_location, next_health, wall)) = 
-                                self.selected_move {
-                            // Duplicates in this vec are fine. Won't be crazy large
-                            self.visited_locations.push(next_location);
-
-                            // Update player location
-                            self.player_location = next_location;
-
-                            // Check if the player dies if moved here
-                            if next_health == 0 {
-                                panic!("DIED!");
-                            }
-
-                            if let Some(broken_wall) = wall {
-                                self.built_walls.remove_item(&broken_wall);
-                            }
-
-
-                            // Set the new health value and texture
-                            self.set_health(next_health);
-
-                            // Reset the selected move
-                            self.selected_move = None;
-
-                            // If we are on a teleport location, go to the Teleport 
-                            // selection location
-                            if [36, 43].contains(&self.player_location) {
-                                info!("Movement -> ChooseTeleport");
-                                self.clickables[ButtonId::TurnNextAction as usize]
-                                    .change_text("Choose teleport".to_string());
-                                self.state = BoardState::ChooseTeleport;
-                            } else { 
-                                // Go to the next state
-                                info!("Movement -> TileEffect");
-                                self.clickables[ButtonId::TurnNextAction as usize]
-                                    .change_text("Check tile effect".to_string());
-                                self.state = BoardState::TileEffect;
-                            }
-                        }
-                    }
-                    BoardState::ShortcutDrawWalls => {
-                        info!("ShortcutDrawWalls -> ShortcutMovement");
-                        self.clickables[ButtonId::TurnNextAction as usize]
-                            .change_text("Shortcut move".to_string());
-                        self.state = BoardState::ShortcutMovement;
-                        self.insert_walls();
-
-                        // Clear the selected walls
-                        self.selected_walls.clear();
-                    }
-                    BoardState::ShortcutMovement => {
-                        if let Some((next_location, next_health, wall)) 
-                                = self.selected_move {
-                            // Duplicates in this vec are fine. Won't be crazy large
-                            self.visited_locations.push(next_location);
-
-                            // Update player location
-                            self.player_location = next_location;
-
-                            // Check if the player dies if moved here
-                            if next_health == 0 {
-                                panic!("DIED!");
-                            }
-
-                            if let Some(broken_wall) = wall {
-                                self.built_walls.remove_item(&broken_wall);
-                            }
-
-                            // Set the new health value and texture
-                            self.set_health(next_health);
-
-                            // Reset the selected move
-                            self.selected_move = None;
-
-                            // If we are on a teleport location, go to the Teleport 
-                            // selection location
-                            if [36, 43].contains(&self.player_location) {
-                                info!("ShortcutMovement -> ChooseTeleport");
-                                self.clickables[ButtonId::TurnNextAction as usize]
-                                    .change_text("Choose shortcut tele".to_string());
-                                self.state = BoardState::ShortcutChooseTeleport;
-                            } else { 
-                                // Go to the next state
-                                info!("ShortcutMovement -> AssignDice");
-                                self.clickables[ButtonId::TurnNextAction as usize]
-                                    .change_text("End Turn".to_string());
-                                self.state = BoardState::ShortcutTileEffect;
-                            }
-                        }
-                    }
-                    BoardState::TileEffect => {
-                        match self.player_location {
-                            40 => self.find_idol(),
-                            45 => self.set_pickaxe(2),
-                            57 => self.find_elixir(),
-                            63 => self.find_machete(),
-                            _ => {}
-                        }
-
-                        info!("TileEffect -> Encounter");
-                        self.clickables[ButtonId::TurnNextAction as usize]
-                            .change_text("Check encounter".to_string());
-                        self.state = BoardState::Encounter;
-                    }
-                    BoardState::ShortcutTileEffect => {
-                        match self.player_location {
-                            40 => self.find_idol(),
-                            45 => self.set_pickaxe(2),
-                            57 => self.find_elixir(),
-                            63 => self.find_machete(),
-                            _ => {}
-                        }
-
-                        info!("ShortcutTileEffect -> EndTurn");
-                        self.clickables[ButtonId::TurnNextAction as usize]
-                            .change_text("End turn".to_string());
-                        self.state = BoardState::EndTurn;
-                    }
-                    BoardState::ChooseTeleport|BoardState::ShortcutChooseTeleport => {
-                        // This is reached if the player is on a teleport location
-                        if let Some(tele_loc) = self.selected_teleport {
-                            self.visited_locations.push(tele_loc);
-                            self.player_location = tele_loc;
-                        }
-
-                        // Reset the selected teleport
-                        self.selected_teleport = None;
-
-                        if self.state == BoardState::ChooseTeleport {
-                            // Move to the next state
-                            info!("ChooseTeleport -> Encounter");
-                            self.clickables[ButtonId::TurnNextAction as usize]
-                                .change_text("Check encounter".to_string());
-                            self.state = BoardState::Encounter;
-                        } else {
-                            // Taking a teleport due to a shortcut will lead 
-                            // back to the assign dice
-                            info!("ShortcutChooseTeleport -> AssignDice");
-                            self.clickables[ButtonId::TurnNextAction as usize]
-                                .change_text("End turn".to_string());
-                            self.state = BoardState::EndTurn;
-                        }
-                    }
-                    BoardState::Encounter => {
-                        info!("Trying encoutner: {:?}", self.encounter);
-                        match self.encounter {
-                            Some(1) => {
-                                // Sneak beast attack
-                                let rand_roll = self.rng.roll_d6();
-                                info!("Sneak beast roll: {}", rand_roll);
-
-                                match (rand_roll, self.current_turn) {
-                                   (1..=2,  1..=6)   => {
-                                       self.set_health(self.health.saturating_sub(2));
-                                   }
-                                   (1..=2,  7..=12)  => {
-                                       self.set_health(self.health.saturating_sub(3));
-                                   }
-                                   (1..=2, 13..=18)  => {
-                                       self.set_health(self.health.saturating_sub(4));
-                                   }
-                                   (3..=4,  1..=6)   => {
-                                       self.set_health(self.health.saturating_sub(3));
-                                   }
-                                   (3..=4,  7..=12)  => {
-                                       self.set_health(self.health.saturating_sub(4));
-                                   }
-                                   (3..=4, 13..=18)  => {
-                                       self.set_health(self.health.saturating_sub(5));
-                                   }
-                                   (5..=6,  1..=6)   => {
-                                       self.set_health(self.health.saturating_sub(4));
-                                   }
-                                   (5..=6,  7..=12)  => {
-                                       self.set_health(self.health.saturating_sub(5));
-                                   }
-                                   (5..=6, 13..=18)  => {
-                                       self.set_health(self.health.saturating_sub(6));
-                                   }
-                                   (_, _) => unreachable!()
-                                }
-                            }
-                            Some(2) => {
-                                // Rest at a Campfire
-                                // +1 health
-                                info!("Rest: +1 health");
-                                if self.health < 6 {
-                                    self.set_health(self.health + 1);
-                                }
-                            }
-                            Some(3) => {
-                                // Beast attack
-                                let rand_roll = self.rng.roll_d6();
-                                info!("Beast attack roll: {}", rand_roll);
-
-                                match (rand_roll, self.current_turn) {
-                                   (1..=2,  1..=6) => {
-                                       self.set_health(self.health.saturating_sub(1));
-                                   }
-                                   (1..=2,  7..=12) => {
-                                       self.set_health(self.health.saturating_sub(2));
-                                   }
-                                   (1..=2, 13..=18) => {
-                                       self.set_health(self.health.saturating_sub(3));
-                                   }
-                                   (3..=4,  1..=6)  => {
-                                       self.set_health(self.health.saturating_sub(2));
-                                   }
-                                   (3..=4,  7..=12) => {
-                                       self.set_health(self.health.saturating_sub(3));
-                                   }
-                                   (3..=4, 13..=18) => {
-                                       self.set_health(self.health.saturating_sub(4));
-                                   }
-                                   (5..=6,  1..=6)  => {
-                                       self.set_health(self.health.saturating_sub(3));
-                                   }
-                                   (5..=6,  7..=12) => {
-                                       self.set_health(self.health.saturating_sub(4));
-                                   }
-                                   (5..=6, 13..=18) => {
-                                       self.set_health(self.health.saturating_sub(5));
-                                   }
-                                   (_, _) => unreachable!()
-                                }
-                            }
-                            Some(4) => {
-                                let val = self.rng.roll_d6();
-                                self.set_next_tile(Some(val));
-                                while self.next_tile == Some(6) {
-                                    let val = self.rng.roll_d6();
-                                    self.set_next_tile(Some(val));
-                                }
-
-                                info!("Shortcut next tile: {:?}", self.next_tile);
-
-                                // Bail early in order to draw a new tile
-                                info!("Encounter -> ShortcutDrawWalls");
-                                self.clickables[ButtonId::TurnNextAction as usize]
-                                    .change_text("Draw shortcut walls".to_string());
-                                self.state = BoardState::ShortcutDrawWalls;
-
-                                // Init the walls
-                                self.wall_orientation = 0;
-                                match self.next_tile {
-                                    Some(1) => [Wall::Left, Wall::Top, Wall::Right]
-                                        .iter()
-                                        .for_each(|x| self.selected_walls.push(*x)),
-                                    Some(2) => [Wall::Left, Wall::Bottom].iter()
-                                        .for_each(|x| self.selected_walls.push(*x)),
-                                    Some(3) => [Wall::Left, Wall::Right].iter()
-                                        .for_each(|x| self.selected_walls.push(*x)),
-                                    Some(4) => [Wall::Left].iter()
-                                        .for_each(|x| self.selected_walls.push(*x)),
-                                    Some(5) => {},
-                                    _ => unreachable!()
-                                }
-                            }
-                            Some(5) => {
-                                // We only pick up an item if we didn't pick up a 
-                                // tile item this turn
-                                if ![40, 45, 57, 63].contains(&self.player_location) {
-                                    match self.rng.roll_d6() {
-                                        1 => {
-                                            info!("New item: Charm");
-                                            self.set_charm(2);
-                                        }
-                                        2 => {
-                                            info!("New item: Machete");
-                                            self.find_machete();
-                                        }
-                                        3 => {
-                                            info!("New item: Pickaxe");
-                                            self.set_pickaxe(2);
-                                        }
-                                        4 => {
-                                            info!("New item: Shotgun");
-                                            self.set_shotgun(2);
-                                        }
-                                        5 => {
-                                            info!("New item: Bandage");
-                                            self.set_bandage(2);
-                                        }
-                                        6 => {
-                                            info!("New item: Elixir");
-                                            self.find_elixir();
-                                        }
-                                        _ => unreachable!()
-                                    }
-                                }
-                            }
-                            Some(6) => {
-                                info!("Fall into a trap!");
-                                match self.current_turn {
-                                    ( 1..=6)  => {
-                                        self.set_health(self.health.saturating_sub(1));
-                                    }
-                                    ( 7..=12) => {
-                                        self.set_health(self.health.saturating_sub(2));
-                                    }
-                                    (13..=18) => {
-                                        self.set_health(self.health.saturating_sub(3));
-                                    }
-                                    _ => unreachable!()
-                                }
-                            }
-                            _ => unreachable!()
-                        }
-
-                        if self.health == 0 {
-                            info!("GAME OVER");
-                            panic!("GAME OVER");
-                        }
-
-                        if self.state != BoardState::ShortcutMovement &&
-                            self.state != BoardState::ShortcutDrawWalls {
-                            // Normal execution
-                            // Jump back to the beginning of the turn
-                            info!("Encounter -> AssignDice");
-                            self.clickables[ButtonId::TurnNextAction as usize]
-                                .change_text("End turn".to_string());
-                            self.state = BoardState::EndTurn;
-                        } 
-                    }
-                    BoardState::EndTurn => {
-                        // Reroll the two dice
-                        let val1 = self.rng.roll_d6();
-                        self.set_die1(Some(val1));
+impl Clickable for Board {
+    type Message = BoardCommand;
 
-                        let val2 = self.rng.roll_d6();
-                        self.set_die2(Some(val2));
+    fn handle_click(&mut self, pos: (f32, f32)) -> Option<Self::Message> {
+        info!("Handle click: {:?}", pos);
 
-                        // Reset the board for the next round 
-                        self.next_tile = None;
-                        self.encounter = None;
+        // Once the game is over, the only option is to start a new one
+        if self.game.is_over() {
+            if self.new_game_button.contains(pos) {
+                return Some(BoardCommand::NewGame);
+            }
 
-                        // Increment the turn
-                        self.current_turn += 1;
+            if self.difficulty_button.contains(pos) {
+                return Some(BoardCommand::NextDifficulty);
+            }
 
-                        // Set the next state
-                        self.clickables[ButtonId::TurnNextAction as usize]
-                            .change_text("Assign dice".to_string());
-                        self.state = BoardState::AssignDice;
-                    }
-                }
+            return None;
+        }
+
+        if self.undo_button.contains(pos) {
+            return Some(BoardCommand::Undo);
+        }
+
+        if self.redo_button.contains(pos) {
+            return Some(BoardCommand::Redo);
+        }
+
+        if self.ai_button.contains(pos) {
+            return Some(BoardCommand::AiMove);
+        }
+
+        if self.policy_button.contains(pos) {
+            return Some(BoardCommand::NextPolicy);
+        }
+
+        if self.hints_button.contains(pos) {
+            return Some(BoardCommand::ToggleHints);
+        }
+
+        self.game_click(pos).map(BoardCommand::Game)
+    }
+
+    fn tooltip(&self, pos: (f32, f32)) -> Option<String> {
+        let game = &self.game;
+
+        // Each item along with what is printed next to it on the board
+        let items = [
+            (ButtonId::Charm,   "Charm: reroll the selected die before placing it".to_string(),
+             Some(game.charm)),
+            (ButtonId::Machete,
+             format!("Machete: blocks {} damage from every beast attack and trap",
+                     MACHETE_ATTACK),
+             None),
+            (ButtonId::Pickaxe, "Pickaxe: break a wall".to_string(), Some(game.pickaxe)),
+            (ButtonId::Shotgun,
+             format!("Shotgun: blocks {} damage from a beast attack", SHOTGUN_ATTACK),
+             Some(game.shotgun)),
+            (ButtonId::Bandage, format!("Bandage: +{} health", BANDAGE_HEAL),
+             Some(game.bandage)),
+            (ButtonId::Elixir,  format!("Elixir: +{} health", ELIXIR_HEAL),
+             Some(game.elixir as usize)),
+            (ButtonId::Idol,    "Idol: escape with it to win".to_string(), None),
+        ];
+
+        let item = items.iter()
+            .find(|(id, _, _)| self.clickables[*id as usize].contains(pos))
+            .map(|(_, text, uses)| match uses {
+                Some(uses) => format!("{}, {} uses left", text, uses),
+                None => text.to_string(),
+            });
+
+        if item.is_some() {
+            return item;
+        }
+
+        let hit = |id: ButtonId| self.clickables[id as usize].contains(pos);
+
+        if hit(ButtonId::Health) {
+            return Some(format!("Health: {} of {}", game.health, MAX_HEALTH));
+        }
+
+        if hit(ButtonId::NextTile) {
+            return Some(match game.next_tile {
+                Some(face) => format!("Next Tile {}: {}", face, next_tile_effect(face)),
+                None => "Next Tile: the die placed here picks the walls drawn around you"
+                    .to_string(),
+            });
+        }
+
+        if hit(ButtonId::Encounter) {
+            return Some(match game.encounter {
+                Some(face) => format!("Encounter {}: {}", face, encounter_effect(game, face)),
+                None => "Encounter: the die placed here picks what happens after moving"
+                    .to_string(),
+            });
+        }
+
+        for &(id, die) in [(ButtonId::Die1, game.die1), (ButtonId::Die2, game.die2)].iter() {
+            if hit(id) {
+                return die.map(|face| {
+                    format!("{} as Next Tile: {}. As Encounter: {}", face,
+                            next_tile_effect(face), encounter_effect(game, face))
+                });
             }
-            BoardMessage::Select(x)  => self.selected = Some(x),
-            BoardMessage::ChooseDie1 => {
-                info!("Selected die = 1");
-                self.selected_die = Some(1);
+        }
+
+        for (i, turn_box) in self.turn_boxes().iter().enumerate() {
+            let turn = i + 1;
+            if turn_box.contains(pos) {
+                let now = if turn == game.current_turn { ", this turn" } else { "" };
+                let (low, high) = game.difficulty.attack_range(
+                    &game.difficulty.beast_attack, turn);
+                return Some(format!("Turn {} of {}{}: beasts deal {}-{} damage", turn,
+                                    game.map.turns, now, low, high));
             }
-            BoardMessage::ChooseDie2 => {
-                info!("Selected die = 1");
-                self.selected_die = Some(2);
+        }
+
+        if hit(ButtonId::TurnNextAction) {
+            return Some("Finish this step of the turn".to_string());
+        }
+
+        let steps = [
+            (ButtonId::TurnAssignDice, BoardState::AssignDice,
+             "Place one die on the Next Tile and the other on the Encounter"),
+            (ButtonId::TurnDrawWalls, BoardState::DrawWalls,
+             "Draw the Next Tile's walls around you, click your tile to rotate them"),
+            (ButtonId::TurnMovement, BoardState::Movement,
+             "Move to a neighboring tile"),
+            (ButtonId::TurnTileEffect, BoardState::TileEffect,
+             "Pick up the item on your tile"),
+            (ButtonId::TurnEncounter, BoardState::Encounter,
+             "Resolve the Encounter die"),
+        ];
+
+        for &(id, state, text) in steps.iter() {
+            if hit(id) {
+                let now = if state == game.state { " (now)" } else { "" };
+                return Some(format!("{}{}", text, now));
             }
-            BoardMessage::ChooseNextTile => {
-                match self.selected_die {
-                    Some(which_die) => {
-                        // Save the old next_tile in case we need to swap die
-                        let old_next_tile = self.next_tile;
-
-                        info!("Old next_tile: {:?}", old_next_tile);
-
-                        // Get the next_tile button
-                        let next_tile_button = &mut self.clickables[ButtonId::NextTile as usize];
-
-                        // Get the face of the die corresponding to the selected die
-                        let new_die_face = match which_die {
-                            1 => self.die1.expect("No die1?"),
-                            2 => self.die2.expect("No die2?"),
-                            _ => unreachable!()
-                        };
-
-                        // Set the next_tile to the value in the selected die
-                        self.next_tile = Some(new_die_face);
-
-                        // Set the enounter button to the selected die
-                        next_tile_button.change_texture(
-                            self.dice_textures[new_die_face]);
-
-
-                        // Reset selected die
-                        self.selected_die = None;
-
-                        // If the next_tile was selected already before, swap the dice. Otherwise,
-                        // clear the selected die
-                        match old_next_tile {
-                            None => {
-                                // Reset die
-                                match which_die {
-                                    1 => self.set_die1(None),
-                                    2 => self.set_die2(None),
-                                    _ => unreachable!()
-                                }
-                            }
-                            Some(old_die) => {
-                                info!("Setting die {} to {}", which_die, old_die);
-
-                                // Swap die
-                                match which_die {
-                                    1 => self.set_die1(Some(old_die)),
-                                    2 => self.set_die2(Some(old_die)),
-                                    _ => unreachable!()
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
+        }
+
+        // Explain what stepping onto each neighbor costs
+        if matches!(game.state, BoardState::Movement | BoardState::ShortcutMovement) {
+            for (wall, tile) in game.get_neighbors() {
+                if !self.tile_button(tile).contains(pos) {
+                    continue;
                 }
-            }
-            BoardMessage::ChooseEncounter => {
-                match self.selected_die {
-                    Some(which_die) => {
-                        // Save the old encounter in case we need to swap die
-                        let old_encounter = self.encounter;
-
-                        info!("Old encounter: {:?}", old_encounter);
-
-                        // Get the encounter button
-                        let encounter_button = &mut self.clickables[ButtonId::Encounter as usize];
-
-                        // Get the face of the die corresponding to the selected die
-                        let new_die_face = match which_die {
-                            1 => self.die1.expect("No die1?"),
-                            2 => self.die2.expect("No die2?"),
-                            _ => unreachable!()
-                        };
-
-                        // Set the encounter to the value in the selected die
-                        self.encounter = Some(new_die_face);
-
-                        // Set the enounter button to the selected die
-                        encounter_button.change_texture(
-                            self.dice_textures[new_die_face]);
-
-
-                        // Reset selected die
-                        self.selected_die = None;
-
-                        // If the encounter was selected already before, swap the dice. Otherwise,
-                        // clear the selected die
-                        match old_encounter {
-                            None => {
-                                // Reset die
-                                match which_die {
-                                    1 => self.set_die1(None),
-                                    2 => self.set_die2(None),
-                                    _ => unreachable!()
-                                }
-                            }
-                            Some(old_die) => {
-                                info!("Setting die {} to {}", which_die, old_die);
-
-                                // Swap die
-                                match which_die {
-                                    1 => self.set_die1(Some(old_die)),
-                                    2 => self.set_die2(Some(old_die)),
-                                    _ => unreachable!()
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
+
+                let item = match game.map.item_at(tile) {
+                    Some(item) => format!(". The {:?} is here", item),
+                    None => String::new(),
+                };
+
+                if !game.is_wall_built(wall) {
+                    return Some(format!("Move here{}", item));
                 }
+
+                let pickaxe = if game.pickaxe > 0 { " or a pickaxe use" } else { "" };
+                return Some(format!("Break the wall here: costs {} health{}{}",
+                                    WALL_BREAK_COST, pickaxe, item));
             }
-            BoardMessage::ChangeWallOrientation => {
-                // Rotate the wall orientation by one
-                self.wall_orientation = (self.wall_orientation + 1) % 4;
-
-                // Clear the current walls to avoid another allocation
-                self.selected_walls.clear();
-
-                // Set the current walls based on the wall orientation
-                match (self.next_tile, self.wall_orientation) {
-                    (Some(1), 0) => [Wall::Left, Wall::Top, Wall::Right]
-                        .iter().for_each(|x| self.selected_walls.push(*x)),
-                    (Some(1), 1) => [Wall::Top, Wall::Right, Wall::Bottom]
-                        .iter().for_each(|x| self.selected_walls.push(*x)),
-                    (Some(1), 2) => [Wall::Right, Wall::Bottom, Wall::Left]
-                        .iter().for_each(|x| self.selected_walls.push(*x)),
-                    (Some(1), 3) => [Wall::Bottom, Wall::Left, Wall::Top]
-                        .iter().for_each(|x| self.selected_walls.push(*x)),
-                    (Some(2), 0) => [Wall::Left, Wall::Bottom]
-                        .iter().for_each(|x| self.selected_walls.push(*x)),
-                    (Some(2), 1) => [Wall::Top, Wall::Left]
-                        .iter().for_each(|x| self.selected_walls.push(*x)),
-                    (Some(2), 2) => [Wall::Right, Wall::Top]
-                        .iter().for_each(|x| self.selected_walls.push(*x)),
-                    (Some(2), 3) => [Wall::Bottom, Wall::Right]
-                        .iter().for_each(|x| self.selected_walls.push(*x)),
-                    (Some(3), 0|2) => [Wall::Left, Wall::Right]
-                        .iter().for_each(|x| self.selected_walls.push(*x)),
-                    (Some(3), 1|3) => [Wall::Top, Wall::Bottom]
-                        .iter().for_each(|x| self.selected_walls.push(*x)),
-                    (Some(4), 0) => [Wall::Left].iter()
-                        .for_each(|x| self.selected_walls.push(*x)),
-                    (Some(4), 1) => [Wall::Top].iter()
-                        .for_each(|x| self.selected_walls.push(*x)),
-                    (Some(4), 2) => [Wall::Right].iter()
-                        .for_each(|x| self.selected_walls.push(*x)),
-                    (Some(4), 3) => [Wall::Bottom].iter()
-                        .for_each(|x| self.selected_walls.push(*x)),
-                    _ => panic!("ChangeWallOrientation: {:?} {:?}", 
-                        self.next_tile, self.wall_orientation), 
-                }
+        }
+
+        None
+    }
+
+    fn handle_message(&mut self, command: Self::Message) {
+        info!("Handling command: {:?}", command);
+
+        let message = match command {
+            BoardCommand::Game(message) => message,
+            BoardCommand::Undo => {
+                self.undo();
+                self.sync_buttons();
+                return;
+            }
+            BoardCommand::Redo => {
+                self.redo();
+                self.sync_buttons();
+                return;
+            }
+            BoardCommand::NewGame => {
+                self.new_game();
+                return;
             }
-            BoardMessage::MoveToLocation((through_wall, next_index)) => {
-                let mut health = self.health;
-                let mut wall = None;
-                if self.is_wall_built(through_wall) {
-                    // Set the selected movement as well as the resulting health
-                    // if this move is selected
-                    let checked_wall = (self.player_location, through_wall);
-                    let curr_wall = self.walls_translation.get(&checked_wall)
-                        .expect(&format!("Wall not in translation: {:?}", 
-                                         checked_wall));
-
-                    health = self.health.saturating_sub(4);
-                    wall = Some(*curr_wall);
+            BoardCommand::NextDifficulty => {
+                self.next_difficulty();
+                self.sync_buttons();
+                return;
+            }
+            BoardCommand::AiMove => {
+                // Send exactly what a player making the same choices would
+                for message in self.policies[self.policy].plan(&self.game) {
+                    self.handle_message(BoardCommand::Game(message));
                 }
-
-                self.selected_move = Some((next_index, health, wall));
-                info!("Selecting move: {:?}", self.selected_move);
+                return;
             }
-            BoardMessage::ChooseTeleport(index) => {
-                self.selected_teleport = Some(index);
-                info!("Selecting teleport location: {:?}", self.selected_move);
+            BoardCommand::NextPolicy => {
+                self.policy = (self.policy + 1) % self.policies.len();
+                self.sync_buttons();
+                return;
             }
+            BoardCommand::ToggleHints => {
+                self.show_hints = !self.show_hints;
+                self.sync_hints();
+                return;
+            }
+        };
+
+        let from = self.game.player_location;
+        let outcomes = self.apply(message);
+        self.autosave(&outcomes);
+        self.animations.start(&outcomes, from);
+
+        if let Some(audio) = &self.audio {
+            audio.play_outcomes(&outcomes);
         }
+
+        for outcome in &outcomes {
+            info!("{:?}", outcome);
+        }
+
+        // Remember what happened so the player can see it on the board, until the next
+        // action that changes anything
+        if !outcomes.is_empty() {
+            let events = outcomes.iter().filter_map(describe_outcome).collect::<Vec<_>>();
+            self.last_event = if events.is_empty() { None } else { Some(events.join(". ")) };
+        }
+
+        self.sync_buttons();
+    }
+}
+
+impl Board {
+    /// Pick the bundled difficulty after the one picked for the next game, wrapping
+    /// around. A difficulty that wasn't bundled goes back to the first one.
+    fn next_difficulty(&mut self) {
+        let difficulties = tables::bundled_difficulties();
+        let index = difficulties.iter()
+            .position(|difficulty| difficulty.name == self.next_difficulty.name)
+            .map_or(0, |index| (index + 1) % difficulties.len());
+        self.next_difficulty = difficulties[index].clone();
+    }
+
+    /// Replace the game with a new one, keeping the board's settings
+    fn new_game(&mut self) {
+        // Keep playing the same temple, at the difficulty picked for the next game
+        let game = GameState::with_rules(Rng::new(), self.game.map.clone(),
+                                         self.next_difficulty.clone());
+        let (autosave, policy, show_hints, hint_depth, theme, animations) =
+            (self.autosave, self.policy, self.show_hints, self.hint_depth, self.theme,
+             self.animations.is_enabled());
+        let audio = self.audio.take();
+        *self = Board::from_game(self.texture, self.dice_textures, game);
+        self.audio = audio;
+        self.theme = theme;
+        self.animations.set_enabled(animations);
+        self.autosave = autosave;
+        self.policy = policy;
+        self.show_hints = show_hints;
+        self.hint_depth = hint_depth;
+        self.sync_buttons();
     }
 }
 
@@ -1211,27 +1227,27 @@ impl Drawable for Board {
     }
 
     fn draw(&self) {
-        // Draw the current image
-        draw_texture_ex(
-            self.texture(),
-            0.,
-            0.,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(vec2(screen_width(), screen_height())),
-                ..Default::default()
-            }
-        );
+        let game = &self.game;
+
+        // Draw the current image, or the board itself from the map
+        match &self.theme {
+            Some(theme) => vector::draw(game, &self.clickables, theme),
+            None => layout::draw_board_texture(self.texture()),
+        }
 
         // Draw the health die
         let health_button = &self.clickables[ButtonId::Health as usize];
         health_button.draw();
 
-
+        // Flash the health die red after taking damage
+        if let Some(flash) = self.animations.damage_flash() {
+            let (x, y, w, h) = health_button.to_screen();
+            draw_rectangle(x, y, w, h, Color::new(1., 0., 0., flash * 0.7));
+        }
 
         // Draw the items
-        for &item in [ButtonId::Charm, ButtonId::Machete, 
-                     ButtonId::Pickaxe, ButtonId::Shotgun, 
+        for &item in [ButtonId::Charm, ButtonId::Machete,
+                     ButtonId::Pickaxe, ButtonId::Shotgun,
                      ButtonId::Bandage, ButtonId::Elixir, ButtonId::Idol].iter() {
             let button = &self.clickables[item as usize];
             button.draw();
@@ -1243,7 +1259,7 @@ impl Drawable for Board {
         self.clickables[ButtonId::TurnNextAction as usize].draw();
 
         // Get the current board state button ID
-        let state_button_id = match self.state {
+        let state_button_id = match game.state {
             BoardState::AssignDice => 31,
             BoardState::DrawWalls => 32,
             BoardState::ShortcutDrawWalls => 32,
@@ -1254,57 +1270,62 @@ impl Drawable for Board {
             BoardState::ChooseTeleport => 34,
             BoardState::ShortcutChooseTeleport => 34,
             BoardState::Encounter => 35,
+            BoardState::DefendAttack => 35,
             BoardState::EndTurn => 30,
+            BoardState::GameOver => 30,
+            BoardState::Victory => 30,
         };
 
         // Draw a rectangle around the current state
         let (x, y, w, h) = self.clickables[state_button_id].to_screen();
         draw_rectangle_lines(x, y, w, h, 8., BLACK);
 
-
         // Draw the next_tile die
-        if let Some(next_tile) = self.next_tile {
-            let mut next_tile_button = &self.clickables[ButtonId::NextTile as usize];
-            next_tile_button.draw();
+        if game.next_tile.is_some() {
+            self.draw_die(ButtonId::NextTile);
         }
 
         // Draw the encounter die
-        if let Some(encounter) = self.encounter {
-            let mut encounter_button = &self.clickables[ButtonId::Encounter as usize];
-            encounter_button.draw();
+        if game.encounter.is_some() {
+            self.clickables[ButtonId::Encounter as usize].draw();
         }
 
         // Mark the turns
-        for turn_box in 10..10 + self.current_turn {
-            let (x, y, w, h) = self.clickables[turn_box].to_screen();
+        for turn_box in self.turn_boxes().iter().take(game.current_turn) {
+            let (x, y, w, h) = turn_box.to_screen();
             draw_rectangle(x, y, w, h, RED);
         }
 
         // Display all the visited locations
-        for &visited in &self.visited_locations {
-            let (x, y, w, h) = self.clickables[visited].to_screen();
+        for &visited in &game.visited_locations {
+            let (x, y, w, h) = self.tile_button(visited).to_screen();
             draw_rectangle(x, y, w, h, color_from_usize(139, 69, 19, 80));
         }
 
-        // Display the current player location
-        let (x, y, w, h) = self.clickables[self.player_location].to_screen();
+        // Display the current player location, sliding in from the last tile
+        let (mut x, mut y, w, h) = self.tile_button(game.player_location).to_screen();
+        if let Some((from, t)) = self.animations.marker() {
+            let (from_x, from_y, _, _) = self.tile_button(from).to_screen();
+            x = from_x + (x - from_x) * t;
+            y = from_y + (y - from_y) * t;
+        }
         draw_rectangle(x, y, w, h, color_from_usize(139, 69, 19, 140));
 
-        if matches!(self.state, BoardState::AssignDice) {
+        if matches!(game.state, BoardState::AssignDice) {
             // Draw the two dice
             let die1_button = &self.clickables[ButtonId::Die1 as usize];
             let die2_button = &self.clickables[ButtonId::Die2 as usize];
 
-            if self.die1.is_some() {
-                die1_button.draw();
+            if game.die1.is_some() {
+                self.draw_die(ButtonId::Die1);
             }
 
-            if self.die2.is_some() {
-                die2_button.draw();
+            if game.die2.is_some() {
+                self.draw_die(ButtonId::Die2);
             }
 
             // Highlight the selected die
-            if let Some(chosen_die) = self.selected_die {
+            if let Some(chosen_die) = game.selected_die {
                 match chosen_die {
                     1 => {
                         let (x, y, w, h) = die1_button.to_screen();
@@ -1320,8 +1341,8 @@ impl Drawable for Board {
         }
 
         // In Assign Die, highlight the selected die
-        if matches!(self.state, BoardState::AssignDice) {
-            if let Some(die) = self.selected_die {
+        if matches!(game.state, BoardState::AssignDice) {
+            if let Some(die) = game.selected_die {
                 let die_id = match die {
                     1 => ButtonId::Die1 as usize,
                     2 => ButtonId::Die2 as usize,
@@ -1334,10 +1355,10 @@ impl Drawable for Board {
         }
 
         // If we are in draw_walls, draw the walls that the player is selecting
-        if matches!(self.state, BoardState::DrawWalls) || 
-                matches!(self.state, BoardState::ShortcutDrawWalls) {
-            let (x, y, w, h) = self.clickables[self.player_location].to_screen();
-            for wall in &self.selected_walls {
+        if matches!(game.state, BoardState::DrawWalls) ||
+                matches!(game.state, BoardState::ShortcutDrawWalls) {
+            let (x, y, w, h) = self.tile_button(game.player_location).to_screen();
+            for wall in &game.selected_walls {
                 match wall {
                     Wall::Top    => draw_line(x,     y,     x + w, y,     6., BLACK),
                     Wall::Right  => draw_line(x + w, y,     x + w, y + h, 6., BLACK),
@@ -1347,49 +1368,106 @@ impl Drawable for Board {
             }
         }
 
-        // If we are in choose_teleport, draw the locations that the player could
+        // If we are choosing a teleport, draw the locations that the player could
         // teleport to
-        if matches!(self.state, BoardState::ChooseTeleport) {
-            for &index in [36, 43].iter() {
-                let (x, y, w, h) = self.clickables[index as usize].to_screen();
+        if matches!(game.state, BoardState::ChooseTeleport
+                                | BoardState::ShortcutChooseTeleport) {
+            for &index in game.map.teleports.iter() {
+                let (x, y, w, h) = self.tile_button(index).to_screen();
                 draw_rectangle(x, y, w, h, color_from_usize(139, 69, 19, 240));
             }
 
-            if let Some(index) = self.selected_teleport {
-                let (x, y, w, h) = self.clickables[index].to_screen();
+            if let Some(index) = game.selected_teleport {
+                let (x, y, w, h) = self.tile_button(index).to_screen();
                 draw_rectangle_lines(x, y, w, h, 10., GREEN);
             }
         }
 
         // If we are in draw_movement, draw the spaces available to the player
-        if matches!(self.state, BoardState::Movement) ||
-                matches!(self.state, BoardState::ShortcutMovement) {
-            for (_wall, index) in self.get_neighbors() {
-                let (x, y, w, h) = self.clickables[index].to_screen();
+        if matches!(game.state, BoardState::Movement) ||
+                matches!(game.state, BoardState::ShortcutMovement) {
+            for (wall, index) in game.get_neighbors() {
+                let (x, y, w, h) = self.tile_button(index).to_screen();
                 draw_rectangle(x, y, w, h, color_from_usize(139, 69, 19, 240));
-            }
-        }
 
-        /*
-        if let Some(select) = self.selected {
-            info!("Drawing {}", select);
-            let selected_rect = &self.clickables[select];
-            selected_rect.draw();
+                // Show the cost of breaking through a wall to get here
+                if game.is_wall_built(wall) {
+                    let cost = if game.pickaxe > 0 {
+                        format!("-{} HP or pickaxe", WALL_BREAK_COST)
+                    } else {
+                        format!("-{} HP", WALL_BREAK_COST)
+                    };
+                    text::draw(&cost, x + 4., y + h / 2., h / 5., WHITE);
+                }
+            }
         }
-        */
 
         // Draw all walls that are currently built
-        for &wall in &self.built_walls {
+        for wall in &game.built_walls {
+            let wall = self.walls_translation[wall];
             let (x, y, w, h) = self.clickables[wall].to_screen();
             draw_rectangle(x, y, w, h, BLACK);
         }
 
         // Highlight the seleted location
-        if let Some((location, _health, _wall)) = self.selected_move {
-            info!("Highlighting selected move: {:?}", location);
-            let (x, y, w, h) = self.clickables[location].to_screen();
+        if let Some(selected) = game.selected_move {
+            let (x, y, w, h) = self.tile_button(selected.location).to_screen();
             draw_rectangle_lines(x, y, w, h, 8., GREEN);
+
+            // Highlight the pickaxe if it will be used to break the wall
+            if selected.pickaxe {
+                let (x, y, w, h) = self.clickables[ButtonId::Pickaxe as usize]
+                    .to_screen();
+                draw_rectangle_lines(x, y, w, h, 8., GREEN);
+            }
         }
 
+        // Show the seed so that the game can be shared or reported
+        let (board_x, board_y, _, board_h) = layout::viewport();
+        text::draw(&format!("Seed: {}", game.seed()), board_x + 5., board_y + board_h - 20.,
+                   20., BLACK);
+
+        // Highlight the shotgun while it can be fired
+        if matches!(game.state, BoardState::DefendAttack) && game.shotgun > 0 {
+            let (x, y, w, h) = self.clickables[ButtonId::Shotgun as usize].to_screen();
+            draw_rectangle_lines(x, y, w, h, 8., GREEN);
+        }
+
+        // Highlight the charm while it can reroll the selected die
+        if matches!(game.state, BoardState::AssignDice) && game.charm > 0
+                && game.selected_die.is_some() {
+            let (x, y, w, h) = self.clickables[ButtonId::Charm as usize].to_screen();
+            draw_rectangle_lines(x, y, w, h, 8., GREEN);
+        }
+
+        // Only offer undo and redo while there is something to go back or forward to
+        if !game.is_over() {
+            self.ai_button.draw();
+            self.policy_button.draw();
+            self.hints_button.draw();
+
+            for panel in &self.hint_panels {
+                if panel.text.as_ref().is_some_and(|(text, _)| !text.is_empty()) {
+                    panel.draw();
+                }
+            }
+
+            if self.history.can_undo() {
+                self.undo_button.draw();
+            }
+
+            if self.history.can_redo() {
+                self.redo_button.draw();
+            }
+        }
+
+        // Explain the current choice to the player, or what just happened
+        if self.prompt().is_some() || self.last_event.is_some() {
+            self.prompt_banner.draw();
+        }
+
+        if game.is_over() {
+            self.draw_results();
+        }
     }
 }
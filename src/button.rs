@@ -1,17 +1,18 @@
 use crate::*;
+use std::cell::Cell;
 
 /// A drawable or clickable rectangle
 pub struct Button {
-    /// X coordinate (in percentage of screen width)
+    /// X coordinate (in percentage of board width)
     pub x: f32,
 
-    /// Y coordinate (in percentage of screen height)
+    /// Y coordinate (in percentage of board height)
     pub y: f32,
 
-    /// Width of the rectangle (in percentage of screen width)
+    /// Width of the rectangle (in percentage of board width)
     pub w: f32,
 
-    /// Height of the rectangle (in percentage of screen height)
+    /// Height of the rectangle (in percentage of board height)
     pub h: f32,
 
     /// Optional text on the Button
@@ -22,7 +23,11 @@ pub struct Button {
     pub texture: Option<Texture2D>,
 
     /// Background color of the button
-    pub background_color: Option<Color>
+    pub background_color: Option<Color>,
+
+    /// Font size the text was last fitted at, along with the (w, h) in pixels it was
+    /// fitted to, so it is only fitted again when the text or screen size changes
+    pub fit: Cell<Option<(f32, f32, f32)>>,
 }
 
 impl std::fmt::Debug for Button {
@@ -39,46 +44,31 @@ impl std::fmt::Debug for Button {
 impl Button {
     pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
         Button { x , y, w, h, text: None, texture: None, 
-            background_color: Some(BLACK) }
+            background_color: Some(BLACK), fit: Cell::new(None) }
     }
 
     pub fn new_with_text(x: f32, y: f32, w: f32, h: f32, text: (String, Color)) -> Self {
         Button { x , y, w, h, text: Some(text), texture: None,
-            background_color: Some(BLACK) }
+            background_color: Some(BLACK), fit: Cell::new(None) }
     }
 
-    pub fn new_with_texture(x: f32, y: f32, w: f32, h: f32, texture: Texture2D) -> Self {
-        Button { x , y, w, h, text: None, texture: Some(texture),
-            background_color: Some(BLACK) }
-    }
     
     /// Converts the percentage coordinates to actual pixels on the current screen
     pub fn to_screen(&self) -> (f32, f32, f32, f32) {
-        // Convert percentages to actual coords
-        let x = self.x * screen_width();
-        let y = self.y * screen_height();
-        let w = self.w * screen_width();
-        let h = self.h * screen_height();
-        (x, y, w, h)
+        let (x, y) = layout::to_screen((self.x, self.y));
+        let (_, _, board_w, board_h) = layout::viewport();
+        (x, y, self.w * board_w, self.h * board_h)
     }
 
     /// Returns true if the given mouse position is in the Button
     pub fn contains(&self, mouse: (f32, f32)) -> bool {
-        let (x, y) = mouse;
-        let min_x = self.x * screen_width();
-        let max_x = (self.x + self.w) * screen_width();
-        let min_y = self.y * screen_height();
-        let max_y = (self.y + self.h) * screen_height();
-        min_x <= x && x <= max_x && min_y <= y && y <= max_y
+        let (x, y) = layout::to_board(mouse);
+        self.x <= x && x <= self.x + self.w && self.y <= y && y <= self.y + self.h
     }
 
     /// Draws the button to the screen
     pub fn draw(&self) {
-        // Convert percentages to actual coords
-        let x = self.x * screen_width();
-        let y = self.y * screen_height();
-        let w = self.w * screen_width();
-        let h = self.h * screen_height();
+        let (x, y, w, h) = self.to_screen();
 
         if let Some(color) = self.background_color {
             draw_rectangle(x, y, w, h, color);
@@ -94,21 +84,16 @@ impl Button {
         }
 
         if let Some((text, color)) = &self.text {
-            let mut best_size = 0.;
-            for size in 1..100 {
-                let (text_width, text_height) = measure_text(text, size as f32);
-                if text_width < w * 0.9 && text_height < h * 0.9 {
-                    best_size = size as f32;
-                    continue;
+            let size = match self.fit.get() {
+                Some((fit_w, fit_h, size)) if fit_w == w && fit_h == h => size,
+                _ => {
+                    let size = text::fit(text, w, h);
+                    self.fit.set(Some((w, h, size)));
+                    size
                 }
-                break;
-            }
+            };
 
-            draw_text(&text, 
-                    x,
-                    y, // - (h / 4.),
-                    best_size,
-                    *color);
+            text::draw_centered(text, (x, y, w, h), size, *color);
         }
     }
 
@@ -117,7 +102,12 @@ impl Button {
         let text = self.text.take();
         let val = match text {
             None => (new_text, BLACK),
-            Some((_, color)) => (new_text, color)
+            Some((old_text, color)) => {
+                if old_text != new_text {
+                    self.fit.set(None);
+                }
+                (new_text, color)
+            }
         };
 
         self.text = Some(val);
@@ -127,7 +117,10 @@ impl Button {
     pub fn change_text_color(&mut self, color: Color) {
         let text = self.text.take();
         let val = match text {
-            None => ("NA".to_string(), color),
+            None => {
+                self.fit.set(None);
+                ("NA".to_string(), color)
+            }
             Some((text, _)) => (text, color)
         };
 
@@ -149,3 +142,18 @@ impl Button {
         self.background_color = Some(color);
     }
 }
+
+/// Draw a tooltip with the given text next to `pos`, kept on the screen
+pub fn draw_tooltip(text: &str, pos: (f32, f32)) {
+    let size = 24.;
+    let extent = text::measure(text, size);
+    let (w, h) = (extent.width + 16., extent.height + 16.);
+
+    // Prefer showing the tooltip above and to the right of the finger or cursor
+    let x = (pos.0 + 12.).min(screen_width() - w).max(0.);
+    let y = (pos.1 - h - 12.).max(0.);
+
+    draw_rectangle(x, y, w, h, BLACK);
+    draw_rectangle_lines(x, y, w, h, 2., WHITE);
+    text::draw(text, x + 8., y + 8. + extent.ascent, size, WHITE);
+}
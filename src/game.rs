@@ -0,0 +1,1191 @@
+//! Headless rules engine for the temple. Nothing in here touches macroquad so a game
+//! can be simulated, tested or scripted without a window.
+use crate::map::Map;
+use crate::rng::Rng;
+use crate::tables::{self, Difficulty};
+use serde::{Deserialize, Serialize};
+
+/// Maximum health of the player
+pub const MAX_HEALTH: usize = 6;
+
+/// Health lost when moving through a built wall
+pub const WALL_BREAK_COST: usize = 4;
+
+/// Health restored by one use of the bandage
+pub const BANDAGE_HEAL: usize = 1;
+
+/// Health restored by drinking the elixir
+pub const ELIXIR_HEAL: usize = 4;
+
+/// Damage absorbed by the machete from every beast attack and trap
+pub const MACHETE_ATTACK: usize = 1;
+
+/// Damage absorbed by firing one shotgun charge at an attacking beast
+pub const SHOTGUN_ATTACK: usize = 3;
+
+/// Wall positions on the board
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Wall {
+    Top,
+    Right,
+    Bottom,
+    Left
+}
+
+/// A wall segment identified by a tile and the side of that tile. Walls shared between
+/// two neighboring tiles are normalized via `Map::wall_key` so both tiles agree on the
+/// key.
+pub type WallKey = (usize, Wall);
+
+/// The actions of a given turn
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoardState {
+    AssignDice,
+    DrawWalls,
+    Movement,
+    TileEffect,
+    Encounter,
+    ChooseTeleport,
+    ShortcutDrawWalls,
+    ShortcutMovement,
+    ShortcutChooseTeleport,
+    ShortcutTileEffect,
+    EndTurn,
+
+    /// A beast attack was rolled and the player may fire the shotgun before the damage
+    /// from the attack table is applied
+    DefendAttack,
+
+    /// The player ran out of health. No further actions are accepted.
+    GameOver,
+
+    /// The player escaped the temple. No further actions are accepted.
+    Victory,
+}
+
+/// Items that can be found in the temple
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Item {
+    Charm,
+    Machete,
+    Pickaxe,
+    Shotgun,
+    Bandage,
+    Elixir,
+    Idol,
+}
+
+/// Sources of damage to the player
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Hazard {
+    SneakBeast,
+    BeastAttack,
+    Trap,
+    BrokenWall,
+}
+
+/// Actions that can be applied to the game. These are triggered by mouse clicks on the
+/// `Board`, but can be sent by anything driving a `GameState`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoardMessage {
+    NextState,
+    Select(usize),
+    ChooseDie1,
+    ChooseDie2,
+    ChooseNextTile,
+    ChooseEncounter,
+    ChangeWallOrientation,
+    MoveToLocation((Wall, usize)),
+    ChooseTeleport(usize),
+
+    /// Spend a shotgun charge on the pending beast attack
+    UseShotgun,
+
+    /// Toggle between spending a pickaxe charge or health to break the wall in the way
+    /// of the selected move
+    TogglePickaxe,
+
+    /// Spend a charm charge to reroll the selected die
+    UseCharm,
+
+    /// Use a bandage charge to heal
+    UseBandage,
+
+    /// Drink the elixir to heal
+    UseElixir,
+}
+
+/// Observable results of applying a `BoardMessage` to the `GameState`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The turn moved on to a new `BoardState`
+    StateChanged(BoardState),
+
+    /// Both dice were rolled for a new turn
+    DiceRolled(usize, usize),
+
+    /// The Next Tile die was rerolled, either from a 6 or from a shortcut
+    NextTileRolled(usize),
+
+    /// Walls were built on the sides of the given tile
+    WallsBuilt(usize, Vec<Wall>),
+
+    /// The player moved to a tile, breaking the given wall on the way
+    Moved { location: usize, broken_wall: Option<WallKey> },
+
+    /// The player teleported to the given tile
+    Teleported(usize),
+
+    /// The player picked up an item
+    ItemFound(Item),
+
+    /// The player was damaged. `roll` is the die rolled on the attack table, if any.
+    Damaged { hazard: Hazard, roll: Option<usize>, amount: usize },
+
+    /// A beast attack was rolled and is waiting on the player to defend against it
+    AttackRolled { hazard: Hazard, roll: usize, amount: usize },
+
+    /// The machete absorbed the given amount of damage
+    MacheteUsed { absorbed: usize },
+
+    /// A charm charge was spent to reroll the given die
+    /// (Which die, old face, new face)
+    CharmUsed(usize, usize, usize),
+
+    /// A pickaxe charge was spent to break the given wall
+    PickaxeUsed(WallKey),
+
+    /// A shotgun charge was spent, absorbing the given amount of damage
+    ShotgunFired { absorbed: usize },
+
+    /// The player used up a charge of an item
+    ItemUsed(Item),
+
+    /// The player regained health
+    Healed(usize),
+
+    /// The player ran out of health
+    Died,
+
+    /// The turn track ran out before the player escaped
+    OutOfTime,
+
+    /// The player made it back out of the temple with the idol
+    Escaped,
+}
+
+/// A move the player has selected but not yet committed to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectedMove {
+    /// Tile being moved to
+    pub location: usize,
+
+    /// Health of the player once the move is made
+    pub health: usize,
+
+    /// Built wall that is broken by this move
+    pub wall: Option<WallKey>,
+
+    /// Is a pickaxe charge spent to break `wall` instead of health
+    pub pickaxe: bool,
+}
+
+/// Full state of a single game, independent of how it is rendered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameState {
+    /// Current state of the game
+    pub state: BoardState,
+
+    /// Current health of the player
+    pub health: usize,
+
+    /// Value of die 1
+    pub die1: Option<usize>,
+
+    /// Value of die 2
+    pub die2: Option<usize>,
+
+    /// Currently selected die
+    pub selected_die: Option<usize>,
+
+    /// Currently selected movement location and the resulting health if the location
+    /// is chosen
+    pub selected_move: Option<SelectedMove>,
+
+    /// Currently selected teleport location
+    pub selected_teleport: Option<usize>,
+
+    /// Current die on Next Tile
+    pub next_tile: Option<usize>,
+
+    /// Current die on Encounter
+    pub encounter: Option<usize>,
+
+    /// Current location of the player as an index into the grid
+    pub player_location: usize,
+
+    /// Current turn
+    pub current_turn: usize,
+
+    /// Currently built walls on the board
+    pub built_walls: Vec<WallKey>,
+
+    /// All visited locations on the board
+    pub visited_locations: Vec<usize>,
+
+    /// Current orientation of walls to place
+    pub wall_orientation: usize,
+
+    /// Current walls selected
+    pub selected_walls: Vec<Wall>,
+
+    /// Does the player have the idol
+    pub idol: bool,
+
+    /// Does the player have the elixir
+    pub elixir: bool,
+
+    /// Does the player have the machete
+    pub machete: bool,
+
+    /// Number of uses for the charm
+    pub charm: usize,
+
+    /// Number of uses for the pickaxe
+    pub pickaxe: usize,
+
+    /// Number of uses for the shotgun
+    pub shotgun: usize,
+
+    /// Number of uses for the bandage
+    pub bandage: usize,
+
+    /// Layout of the temple this game is played in
+    pub map: Map,
+
+    /// Encounter and damage tables used by this game
+    pub difficulty: Difficulty,
+
+    /// Beast attack waiting to be defended against
+    /// (Hazard, roll on the attack table, damage)
+    pub pending_attack: Option<(Hazard, usize, usize)>,
+
+    /// Local rng
+    rng: Rng,
+}
+
+/// Returns the walls drawn by a Next Tile face at the given orientation
+pub fn walls_for_tile(next_tile: usize, orientation: usize) -> &'static [Wall] {
+    match (next_tile, orientation % 4) {
+        (1, 0) => &[Wall::Left, Wall::Top, Wall::Right],
+        (1, 1) => &[Wall::Top, Wall::Right, Wall::Bottom],
+        (1, 2) => &[Wall::Right, Wall::Bottom, Wall::Left],
+        (1, 3) => &[Wall::Bottom, Wall::Left, Wall::Top],
+        (2, 0) => &[Wall::Left, Wall::Bottom],
+        (2, 1) => &[Wall::Top, Wall::Left],
+        (2, 2) => &[Wall::Right, Wall::Top],
+        (2, 3) => &[Wall::Bottom, Wall::Right],
+        (3, 0|2) => &[Wall::Left, Wall::Right],
+        (3, 1|3) => &[Wall::Top, Wall::Bottom],
+        (4, 0) => &[Wall::Left],
+        (4, 1) => &[Wall::Top],
+        (4, 2) => &[Wall::Right],
+        (4, 3) => &[Wall::Bottom],
+        (5, _) => &[],
+        _ => panic!("No walls for next tile: {} {}", next_tile, orientation),
+    }
+}
+
+impl GameState {
+    /// Create a new game in the bundled temple using the default bundled difficulty
+    pub fn new(rng: Rng) -> Self {
+        GameState::with_difficulty(rng, tables::bundled_difficulties().remove(0))
+    }
+
+    /// Create a new game in the bundled temple using the given encounter tables
+    pub fn with_difficulty(rng: Rng, difficulty: Difficulty) -> Self {
+        GameState::with_rules(rng, Map::bundled(), difficulty)
+    }
+
+    /// Create a new game in the given temple using the given encounter tables
+    pub fn with_rules(mut rng: Rng, map: Map, difficulty: Difficulty) -> Self {
+        let die1 = Some(rng.roll_d6());
+        let die2 = Some(rng.roll_d6());
+
+        GameState {
+            state: BoardState::AssignDice,
+            health: MAX_HEALTH,
+            die1,
+            die2,
+            selected_die: None,
+            selected_move: None,
+            selected_teleport: None,
+            next_tile: None,
+            encounter: None,
+            player_location: map.start,
+            current_turn: 1,
+            built_walls: Vec::new(),
+            visited_locations: vec![map.start],
+            wall_orientation: 0,
+            selected_walls: Vec::new(),
+            idol: false,
+            elixir: false,
+            machete: false,
+            charm: 0,
+            pickaxe: 0,
+            shotgun: 0,
+            bandage: 0,
+            map,
+            difficulty,
+            pending_attack: None,
+            rng,
+        }
+    }
+
+    /// Returns the seed of the rng driving this game. Starting a new game with the same
+    /// seed and the same actions replays this game exactly.
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    /// Returns the rng driving this game
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    /// Make every following roll come from `rolls` instead of the rng. Used to look
+    /// ahead at each possible outcome of an action.
+    pub fn script_rolls(&mut self, rolls: Vec<usize>) {
+        self.rng.script(rolls);
+    }
+
+    /// Returns true if the game needed more rolls than were given to `script_rolls`
+    pub fn script_exhausted(&self) -> bool {
+        self.rng.script_exhausted()
+    }
+
+    /// Returns the number of random values drawn so far. If this changes after an
+    /// action, that action revealed something random.
+    pub fn rolls(&self) -> u64 {
+        self.rng.count()
+    }
+
+    /// Returns the neighbors of the current location
+    pub fn get_neighbors(&self) -> Vec<(Wall, usize)> {
+        self.map.neighbors(self.player_location)
+    }
+
+    /// Check if the given wall is built for the current player location
+    pub fn is_wall_built(&self, wall: Wall) -> bool {
+        self.built_walls.contains(&self.map.wall_key(self.player_location, wall))
+    }
+
+    /// Returns true if the player is currently standing on a teleport tile
+    pub fn on_teleport(&self) -> bool {
+        self.map.teleports.contains(&self.player_location)
+    }
+
+    /// Set the current wall orientation at the current location
+    fn insert_walls(&mut self, outcomes: &mut Vec<Outcome>) {
+        for &wall in &self.selected_walls {
+            let key = self.map.wall_key(self.player_location, wall);
+            self.built_walls.push(key);
+        }
+
+        outcomes.push(Outcome::WallsBuilt(self.player_location,
+                                          self.selected_walls.clone()));
+        self.selected_walls.clear();
+    }
+
+    /// Reset the wall orientation and select the walls for the current next tile
+    fn init_walls(&mut self) {
+        self.wall_orientation = 0;
+        self.selected_walls.clear();
+        let next_tile = self.next_tile.expect("No next tile to draw walls for");
+        self.selected_walls.extend_from_slice(walls_for_tile(next_tile, 0));
+    }
+
+    /// Roll the next tile until a non-6 is found
+    fn reroll_next_tile(&mut self, outcomes: &mut Vec<Outcome>) {
+        loop {
+            let val = self.rng.roll_d6();
+            self.next_tile = Some(val);
+            outcomes.push(Outcome::NextTileRolled(val));
+
+            // A 6 causes a reroll until not a 6 is found
+            if val != 6 {
+                break;
+            }
+        }
+    }
+
+    /// Transition to the given state
+    fn set_state(&mut self, state: BoardState, outcomes: &mut Vec<Outcome>) {
+        self.state = state;
+
+        // A move picked in one state must not be committed by a later one
+        self.selected_move = None;
+        outcomes.push(Outcome::StateChanged(state));
+    }
+
+    /// Apply damage from the given hazard
+    fn damage(&mut self, hazard: Hazard, roll: Option<usize>, amount: usize,
+              outcomes: &mut Vec<Outcome>) {
+        self.health = self.health.saturating_sub(amount);
+        outcomes.push(Outcome::Damaged { hazard, roll, amount });
+    }
+
+    /// A beast attacks the player. If the player is able to defend themselves, the
+    /// attack is held until they choose whether to, otherwise the damage is applied.
+    fn attack(&mut self, hazard: Hazard, roll: usize, amount: usize,
+              outcomes: &mut Vec<Outcome>) {
+        let amount = self.swing_machete(amount, outcomes);
+        if amount == 0 {
+            return;
+        }
+
+        if self.shotgun > 0 {
+            self.pending_attack = Some((hazard, roll, amount));
+            outcomes.push(Outcome::AttackRolled { hazard, roll, amount });
+            self.set_state(BoardState::DefendAttack, outcomes);
+            return;
+        }
+
+        self.damage(hazard, Some(roll), amount, outcomes);
+    }
+
+    /// Reduce incoming damage with the machete, if the player has it. Returns the damage
+    /// left over.
+    fn swing_machete(&mut self, amount: usize, outcomes: &mut Vec<Outcome>) -> usize {
+        if !self.machete || amount == 0 {
+            return amount;
+        }
+
+        let absorbed = amount.min(MACHETE_ATTACK);
+        outcomes.push(Outcome::MacheteUsed { absorbed });
+        amount - absorbed
+    }
+
+    /// Apply the pending beast attack, reduced by `absorbed`, and end the turn
+    fn finish_attack(&mut self, absorbed: usize, outcomes: &mut Vec<Outcome>) {
+        let (hazard, roll, amount) = self.pending_attack.take()
+            .expect("No pending attack to finish");
+
+        let amount = amount.saturating_sub(absorbed);
+        if amount > 0 {
+            self.damage(hazard, Some(roll), amount, outcomes);
+        }
+
+        if self.check_death(outcomes) {
+            return;
+        }
+
+        self.set_state(BoardState::EndTurn, outcomes);
+    }
+
+    /// Give the player the given item
+    fn give_item(&mut self, item: Item, outcomes: &mut Vec<Outcome>) {
+        match item {
+            Item::Charm   => self.charm = 2,
+            Item::Machete => self.machete = true,
+            Item::Pickaxe => self.pickaxe = 2,
+            Item::Shotgun => self.shotgun = 2,
+            Item::Bandage => self.bandage = 2,
+            Item::Elixir  => self.elixir = true,
+            Item::Idol    => self.idol = true,
+        }
+
+        outcomes.push(Outcome::ItemFound(item));
+    }
+
+    /// Use a healing item, restoring health up to `MAX_HEALTH`. Healing is refused
+    /// outside of the states that allow it, if the item isn't held or if the player is
+    /// already at full health.
+    fn heal(&mut self, item: Item, outcomes: &mut Vec<Outcome>) {
+        if !self.can_heal() || self.health >= MAX_HEALTH {
+            return;
+        }
+
+        let amount = match item {
+            Item::Bandage if self.bandage > 0 => {
+                self.bandage -= 1;
+                BANDAGE_HEAL
+            }
+            Item::Elixir if self.elixir => {
+                self.elixir = false;
+                ELIXIR_HEAL
+            }
+            _ => return
+        };
+
+        let healed = amount.min(MAX_HEALTH - self.health);
+        self.health += healed;
+        outcomes.push(Outcome::ItemUsed(item));
+        outcomes.push(Outcome::Healed(healed));
+
+        // The health after the selected move depends on the current health
+        self.refresh_selected_move();
+    }
+
+    /// Recompute the resulting health of the selected move
+    fn refresh_selected_move(&mut self) {
+        let health = self.health;
+        if let Some(selected) = &mut self.selected_move {
+            selected.health = if selected.wall.is_some() && !selected.pickaxe {
+                health.saturating_sub(WALL_BREAK_COST)
+            } else {
+                health
+            };
+        }
+    }
+
+    /// Move the player to the currently selected move
+    fn commit_move(&mut self, outcomes: &mut Vec<Outcome>) -> bool {
+        let SelectedMove { location: next_location, health: next_health, wall, pickaxe } =
+            match self.selected_move {
+                Some(selected) => selected,
+                None => return false
+            };
+
+        // Duplicates in this vec are fine. Won't be crazy large
+        self.visited_locations.push(next_location);
+
+        // Update player location
+        self.player_location = next_location;
+
+        if let Some(broken_wall) = wall {
+            self.built_walls.retain(|&built| built != broken_wall);
+
+            if pickaxe {
+                self.pickaxe -= 1;
+                outcomes.push(Outcome::PickaxeUsed(broken_wall));
+            } else {
+                outcomes.push(Outcome::Damaged {
+                    hazard: Hazard::BrokenWall,
+                    roll:   None,
+                    amount: self.health - next_health
+                });
+            }
+        }
+
+        // Set the new health value
+        self.health = next_health;
+
+        // Reset the selected move
+        self.selected_move = None;
+
+        outcomes.push(Outcome::Moved { location: next_location, broken_wall: wall });
+        true
+    }
+
+    /// End the game if the player is out of health. Returns true if the player died.
+    fn check_death(&mut self, outcomes: &mut Vec<Outcome>) -> bool {
+        if self.health > 0 {
+            return false;
+        }
+
+        outcomes.push(Outcome::Died);
+        self.set_state(BoardState::GameOver, outcomes);
+        true
+    }
+
+    /// Win the game if the player reached an exit with the idol. Returns true if the
+    /// player escaped.
+    fn check_escape(&mut self, outcomes: &mut Vec<Outcome>) -> bool {
+        if !self.idol || !self.map.exits.contains(&self.player_location) {
+            return false;
+        }
+
+        outcomes.push(Outcome::Escaped);
+        self.set_state(BoardState::Victory, outcomes);
+        true
+    }
+
+    /// Returns true if the game has ended, either by death or by escaping
+    pub fn is_over(&self) -> bool {
+        matches!(self.state, BoardState::GameOver | BoardState::Victory)
+    }
+
+    /// Returns true if healing items can be used in the current state. Healing is
+    /// allowed while assigning dice, moving and checking the tile effect, but not once
+    /// an encounter has been rolled and its damage shown.
+    pub fn can_heal(&self) -> bool {
+        matches!(self.state, BoardState::AssignDice
+                             | BoardState::Movement | BoardState::ShortcutMovement
+                             | BoardState::TileEffect | BoardState::ShortcutTileEffect)
+    }
+
+    /// Returns the number of distinct tiles the player has stood on
+    pub fn tiles_visited(&self) -> usize {
+        let mut visited = self.visited_locations.clone();
+        visited.sort();
+        visited.dedup();
+        visited.len()
+    }
+
+    /// Returns the items the player is currently holding
+    pub fn items_held(&self) -> Vec<Item> {
+        let mut items = Vec::new();
+        if self.charm   > 0 { items.push(Item::Charm); }
+        if self.machete     { items.push(Item::Machete); }
+        if self.pickaxe > 0 { items.push(Item::Pickaxe); }
+        if self.shotgun > 0 { items.push(Item::Shotgun); }
+        if self.bandage > 0 { items.push(Item::Bandage); }
+        if self.elixir      { items.push(Item::Elixir); }
+        if self.idol        { items.push(Item::Idol); }
+        items
+    }
+
+    /// Check the effect of the tile the player is standing on
+    fn tile_effect(&mut self, outcomes: &mut Vec<Outcome>) {
+        let location = self.player_location;
+        if let Some(item) = self.map.item_at(location) {
+            self.give_item(item, outcomes);
+        }
+    }
+
+    /// Resolve the die on the Encounter space
+    fn resolve_encounter(&mut self, outcomes: &mut Vec<Outcome>) {
+        match self.encounter {
+            Some(1) => {
+                // Sneak beast attack
+                let rand_roll = self.rng.roll_d6();
+                let damage = self.difficulty.attack_damage(
+                    &self.difficulty.sneak_beast, rand_roll, self.current_turn);
+
+                self.attack(Hazard::SneakBeast, rand_roll, damage, outcomes);
+            }
+            Some(2) => {
+                // Rest at a Campfire
+                if self.health < MAX_HEALTH {
+                    let healed = self.difficulty.campfire_heal
+                        .min(MAX_HEALTH - self.health);
+                    self.health += healed;
+                    outcomes.push(Outcome::Healed(healed));
+                }
+            }
+            Some(3) => {
+                // Beast attack
+                let rand_roll = self.rng.roll_d6();
+                let damage = self.difficulty.attack_damage(
+                    &self.difficulty.beast_attack, rand_roll, self.current_turn);
+
+                self.attack(Hazard::BeastAttack, rand_roll, damage, outcomes);
+            }
+            Some(4) => {
+                // Shortcut: draw a brand new tile and move again
+                self.reroll_next_tile(outcomes);
+                self.init_walls();
+                self.set_state(BoardState::ShortcutDrawWalls, outcomes);
+            }
+            Some(5) => {
+                // We only pick up an item if we didn't pick up a
+                // tile item this turn
+                if self.map.item_at(self.player_location).is_none() {
+                    let item = match self.rng.roll_d6() {
+                        1 => Item::Charm,
+                        2 => Item::Machete,
+                        3 => Item::Pickaxe,
+                        4 => Item::Shotgun,
+                        5 => Item::Bandage,
+                        6 => Item::Elixir,
+                        _ => unreachable!()
+                    };
+
+                    self.give_item(item, outcomes);
+                }
+            }
+            Some(6) => {
+                // Fall into a trap
+                let damage = self.difficulty.trap_damage(self.current_turn);
+
+                let damage = self.swing_machete(damage, outcomes);
+                if damage > 0 {
+                    self.damage(Hazard::Trap, None, damage, outcomes);
+                }
+            }
+            _ => unreachable!()
+        }
+
+        if self.check_death(outcomes) {
+            return;
+        }
+
+        if !matches!(self.state, BoardState::ShortcutDrawWalls |
+                                 BoardState::DefendAttack) {
+            // Normal execution
+            // Jump to the end of the turn
+            self.set_state(BoardState::EndTurn, outcomes);
+        }
+    }
+
+    /// Move the selected die onto either the Next Tile or Encounter space. If the space
+    /// was already filled, the old die is swapped back into the selected die's slot.
+    fn assign_selected_die(&mut self, to_next_tile: bool) {
+        let which_die = match self.selected_die.take() {
+            Some(which_die) => which_die,
+            None => return
+        };
+
+        // Get the face of the die corresponding to the selected die
+        let new_die_face = match which_die {
+            1 => self.die1.expect("No die1?"),
+            2 => self.die2.expect("No die2?"),
+            _ => unreachable!()
+        };
+
+        // Place the die and save the old value in case we need to swap die
+        let slot = if to_next_tile { &mut self.next_tile } else { &mut self.encounter };
+        let old_die = slot.replace(new_die_face);
+
+        // If the slot was filled already before, swap the dice. Otherwise, clear the
+        // selected die
+        match which_die {
+            1 => self.die1 = old_die,
+            2 => self.die2 = old_die,
+            _ => unreachable!()
+        }
+    }
+
+    /// Advance the turn to the next `BoardState`
+    fn next_state(&mut self, outcomes: &mut Vec<Outcome>) {
+        match self.state {
+            BoardState::AssignDice => {
+                if self.die1.is_none() && self.die2.is_none() {
+                    // Rerolling 6 on Next Tile
+                    if self.next_tile == Some(6) {
+                        self.reroll_next_tile(outcomes);
+                    }
+
+                    self.init_walls();
+                    self.set_state(BoardState::DrawWalls, outcomes);
+                }
+            }
+            BoardState::DrawWalls => {
+                self.insert_walls(outcomes);
+                self.set_state(BoardState::Movement, outcomes);
+            }
+            BoardState::ShortcutDrawWalls => {
+                self.insert_walls(outcomes);
+                self.set_state(BoardState::ShortcutMovement, outcomes);
+            }
+            BoardState::Movement => {
+                if self.commit_move(outcomes) && !self.check_death(outcomes)
+                        && !self.check_escape(outcomes) {
+                    // If we are on a teleport location, go to the Teleport
+                    // selection location
+                    if self.on_teleport() {
+                        self.set_state(BoardState::ChooseTeleport, outcomes);
+                    } else {
+                        self.set_state(BoardState::TileEffect, outcomes);
+                    }
+                }
+            }
+            BoardState::ShortcutMovement => {
+                if self.commit_move(outcomes) && !self.check_death(outcomes)
+                        && !self.check_escape(outcomes) {
+                    if self.on_teleport() {
+                        self.set_state(BoardState::ShortcutChooseTeleport, outcomes);
+                    } else {
+                        self.set_state(BoardState::ShortcutTileEffect, outcomes);
+                    }
+                }
+            }
+            BoardState::TileEffect => {
+                self.tile_effect(outcomes);
+                self.set_state(BoardState::Encounter, outcomes);
+            }
+            BoardState::ShortcutTileEffect => {
+                self.tile_effect(outcomes);
+                self.set_state(BoardState::EndTurn, outcomes);
+            }
+            BoardState::ChooseTeleport|BoardState::ShortcutChooseTeleport => {
+                // This is reached if the player is on a teleport location
+                if let Some(tele_loc) = self.selected_teleport.take() {
+                    self.visited_locations.push(tele_loc);
+                    self.player_location = tele_loc;
+                    outcomes.push(Outcome::Teleported(tele_loc));
+
+                    if self.check_escape(outcomes) {
+                        return;
+                    }
+                }
+
+                if self.state == BoardState::ChooseTeleport {
+                    self.set_state(BoardState::Encounter, outcomes);
+                } else {
+                    // Taking a teleport due to a shortcut ends the turn
+                    self.set_state(BoardState::EndTurn, outcomes);
+                }
+            }
+            BoardState::Encounter => {
+                self.resolve_encounter(outcomes);
+            }
+            BoardState::DefendAttack => {
+                // Take the full hit
+                self.finish_attack(0, outcomes);
+            }
+            BoardState::EndTurn => {
+                // The last box on the turn track was used without escaping
+                if self.current_turn >= self.map.turns {
+                    outcomes.push(Outcome::OutOfTime);
+                    self.set_state(BoardState::GameOver, outcomes);
+                    return;
+                }
+
+                // Reroll the two dice
+                let val1 = self.rng.roll_d6();
+                let val2 = self.rng.roll_d6();
+                self.die1 = Some(val1);
+                self.die2 = Some(val2);
+                outcomes.push(Outcome::DiceRolled(val1, val2));
+
+                // Reset the board for the next round
+                self.next_tile = None;
+                self.encounter = None;
+
+                // Increment the turn
+                self.current_turn += 1;
+
+                self.set_state(BoardState::AssignDice, outcomes);
+            }
+            BoardState::GameOver | BoardState::Victory => {}
+        }
+    }
+
+    /// Apply the given action to the game, returning everything that happened because
+    /// of it
+    pub fn handle_message(&mut self, message: BoardMessage) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+
+        // A finished game no longer changes
+        if self.is_over() {
+            return outcomes;
+        }
+
+        match message {
+            BoardMessage::NextState  => self.next_state(&mut outcomes),
+            BoardMessage::Select(_)  => {}
+            // Only dice that are still to be assigned can be selected
+            BoardMessage::ChooseDie1 => if self.die1.is_some() {
+                self.selected_die = Some(1);
+            }
+            BoardMessage::ChooseDie2 => if self.die2.is_some() {
+                self.selected_die = Some(2);
+            }
+            BoardMessage::ChooseNextTile  => self.assign_selected_die(true),
+            BoardMessage::ChooseEncounter => self.assign_selected_die(false),
+            BoardMessage::ChangeWallOrientation => {
+                // Walls are only rotated while they are being drawn, once the Next Tile
+                // die is placed
+                let next_tile = match (self.state, self.next_tile) {
+                    (BoardState::DrawWalls | BoardState::ShortcutDrawWalls,
+                     Some(next_tile)) => next_tile,
+                    _ => return outcomes
+                };
+
+                // Rotate the wall orientation by one
+                self.wall_orientation = (self.wall_orientation + 1) % 4;
+
+                // Set the current walls based on the wall orientation
+                self.selected_walls.clear();
+                self.selected_walls.extend_from_slice(
+                    walls_for_tile(next_tile, self.wall_orientation));
+            }
+            BoardMessage::MoveToLocation((through_wall, next_index)) => {
+                // Only a neighbor of the current tile can be moved to, while moving
+                if !matches!(self.state, BoardState::Movement | BoardState::ShortcutMovement)
+                        || !self.get_neighbors().contains(&(through_wall, next_index)) {
+                    return outcomes;
+                }
+
+                // Set the selected movement as well as the resulting health
+                // if this move is selected
+                let mut health = self.health;
+                let mut wall = None;
+                if self.is_wall_built(through_wall) {
+                    health = self.health.saturating_sub(WALL_BREAK_COST);
+                    wall = Some(self.map.wall_key(self.player_location, through_wall));
+                }
+
+                self.selected_move = Some(SelectedMove {
+                    location: next_index,
+                    health,
+                    wall,
+                    pickaxe: false
+                });
+            }
+            BoardMessage::TogglePickaxe => {
+                if !matches!(self.state, BoardState::Movement | BoardState::ShortcutMovement) {
+                    return outcomes;
+                }
+
+                let pickaxe = self.pickaxe;
+                if let Some(selected) = &mut self.selected_move {
+                    if selected.wall.is_some() && (selected.pickaxe || pickaxe > 0) {
+                        selected.pickaxe = !selected.pickaxe;
+                    }
+                }
+
+                self.refresh_selected_move();
+            }
+            BoardMessage::UseCharm => {
+                // The charm rerolls one of the dice that is still to be assigned
+                if self.state == BoardState::AssignDice && self.charm > 0 {
+                    let die = match self.selected_die {
+                        Some(1) => &mut self.die1,
+                        Some(2) => &mut self.die2,
+                        _ => return outcomes
+                    };
+
+                    if let Some(old_face) = *die {
+                        let new_face = self.rng.roll_d6();
+                        *die = Some(new_face);
+                        self.charm -= 1;
+                        outcomes.push(Outcome::CharmUsed(
+                            self.selected_die.unwrap(), old_face, new_face));
+                    }
+                }
+            }
+            BoardMessage::UseBandage => self.heal(Item::Bandage, &mut outcomes),
+            BoardMessage::UseElixir  => self.heal(Item::Elixir, &mut outcomes),
+            BoardMessage::ChooseTeleport(index) => {
+                // Only a cave can be teleported to, while choosing a teleport
+                if matches!(self.state, BoardState::ChooseTeleport
+                                        | BoardState::ShortcutChooseTeleport)
+                        && self.map.teleports.contains(&index) {
+                    self.selected_teleport = Some(index);
+                }
+            }
+            BoardMessage::UseShotgun => {
+                if self.state == BoardState::DefendAttack && self.shotgun > 0 {
+                    self.shotgun -= 1;
+
+                    let (_, _, amount) = self.pending_attack
+                        .expect("No pending attack to shoot");
+                    outcomes.push(Outcome::ShotgunFired {
+                        absorbed: amount.min(SHOTGUN_ATTACK)
+                    });
+
+                    self.finish_attack(SHOTGUN_ATTACK, &mut outcomes);
+                }
+            }
+        }
+
+        outcomes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a new game in the bundled temple with the given dice to assign
+    fn game_with_dice(die1: usize, die2: usize) -> GameState {
+        let mut game = GameState::new(Rng::from_seed(0));
+        game.die1 = Some(die1);
+        game.die2 = Some(die2);
+        game
+    }
+
+    /// Place die 1 on the Next Tile and die 2 on the Encounter
+    fn assign_dice(game: &mut GameState) {
+        for &message in [BoardMessage::ChooseDie1, BoardMessage::ChooseNextTile,
+                         BoardMessage::ChooseDie2, BoardMessage::ChooseEncounter].iter() {
+            game.handle_message(message);
+        }
+    }
+
+    /// Returns a neighbor of the player that can be walked to without breaking a wall
+    /// and isn't a cave
+    fn open_neighbor(game: &GameState) -> (Wall, usize) {
+        game.get_neighbors().into_iter()
+            .find(|&(wall, tile)| !game.is_wall_built(wall)
+                                  && !game.map.teleports.contains(&tile))
+            .expect("No open neighbor")
+    }
+
+    #[test]
+    fn plays_through_a_turn() {
+        // Two walls on opposite sides, then rest at a campfire
+        let mut game = game_with_dice(3, 2);
+        assign_dice(&mut game);
+        assert_eq!(game.state, BoardState::AssignDice);
+
+        game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::DrawWalls);
+
+        game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::Movement);
+        assert_eq!(game.built_walls.len(), 2);
+
+        let neighbor = open_neighbor(&game);
+        game.handle_message(BoardMessage::MoveToLocation(neighbor));
+        game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::TileEffect);
+        assert_eq!(game.player_location, neighbor.1);
+
+        game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::Encounter);
+
+        game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::EndTurn);
+
+        let outcomes = game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::AssignDice);
+        assert_eq!(game.current_turn, 2);
+        assert!(outcomes.iter().any(|outcome| matches!(outcome, Outcome::DiceRolled(..))));
+    }
+
+    #[test]
+    fn dies_when_health_runs_out() {
+        let mut game = game_with_dice(1, 1);
+        game.state = BoardState::Encounter;
+        game.encounter = Some(6);
+        game.health = 1;
+
+        let outcomes = game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::GameOver);
+        assert_eq!(game.health, 0);
+        assert!(outcomes.contains(&Outcome::Died));
+
+        // A finished game no longer changes
+        assert!(game.handle_message(BoardMessage::NextState).is_empty());
+    }
+
+    #[test]
+    fn escapes_through_an_exit_with_the_idol() {
+        let mut game = game_with_dice(1, 1);
+        let exit = *game.map.exits.iter().find(|&&exit| exit != game.map.start).unwrap();
+        let (_, from) = game.map.neighbors(exit)[0];
+        let wall = game.map.neighbors(from).into_iter()
+            .find(|&(_, tile)| tile == exit)
+            .map(|(wall, _)| wall)
+            .unwrap();
+
+        game.state = BoardState::Movement;
+        game.player_location = from;
+        game.idol = true;
+
+        game.handle_message(BoardMessage::MoveToLocation((wall, exit)));
+        let outcomes = game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::Victory);
+        assert!(outcomes.contains(&Outcome::Escaped));
+    }
+
+    #[test]
+    fn runs_out_of_time_after_the_last_turn() {
+        let mut game = game_with_dice(1, 1);
+        game.state = BoardState::EndTurn;
+        game.current_turn = game.map.turns;
+
+        let outcomes = game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::GameOver);
+        assert!(outcomes.contains(&Outcome::OutOfTime));
+    }
+
+    #[test]
+    fn walls_only_rotate_while_drawing_them() {
+        // A 6 on the Next Tile is only rerolled when moving on to drawing the walls
+        let mut game = game_with_dice(6, 2);
+        assign_dice(&mut game);
+        game.handle_message(BoardMessage::ChangeWallOrientation);
+        assert_eq!(game.wall_orientation, 0);
+
+        let mut game = game_with_dice(3, 2);
+        assign_dice(&mut game);
+        game.handle_message(BoardMessage::NextState);
+        game.handle_message(BoardMessage::ChangeWallOrientation);
+        assert_eq!(game.wall_orientation, 1);
+    }
+
+    #[test]
+    fn moves_only_to_neighbors_while_moving() {
+        // The idol is far from the start, so this must not move the player there
+        let mut game = game_with_dice(3, 2);
+        game.handle_message(BoardMessage::MoveToLocation((Wall::Top, 4)));
+        assert_eq!(game.selected_move, None);
+
+        assign_dice(&mut game);
+        game.handle_message(BoardMessage::NextState);
+        game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::Movement);
+
+        game.handle_message(BoardMessage::MoveToLocation((Wall::Top, 4)));
+        assert_eq!(game.selected_move, None);
+        game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::Movement);
+        assert_eq!(game.player_location, game.map.start);
+
+        let neighbor = open_neighbor(&game);
+        game.handle_message(BoardMessage::MoveToLocation(neighbor));
+        assert_eq!(game.selected_move.map(|selected| selected.location), Some(neighbor.1));
+    }
+
+    #[test]
+    fn teleports_only_to_caves_while_choosing() {
+        let mut game = game_with_dice(1, 1);
+        let cave = game.map.teleports[1];
+        game.handle_message(BoardMessage::ChooseTeleport(cave));
+        assert_eq!(game.selected_teleport, None);
+
+        game.state = BoardState::ChooseTeleport;
+        game.player_location = game.map.teleports[0];
+        game.handle_message(BoardMessage::ChooseTeleport(game.map.start));
+        assert_eq!(game.selected_teleport, None);
+
+        game.handle_message(BoardMessage::ChooseTeleport(cave));
+        assert_eq!(game.selected_teleport, Some(cave));
+    }
+
+    #[test]
+    fn pickaxe_only_toggles_while_moving() {
+        let mut game = game_with_dice(1, 1);
+        game.pickaxe = 1;
+        game.state = BoardState::Movement;
+        let neighbor = open_neighbor(&game);
+        game.built_walls.push(game.map.wall_key(game.player_location, neighbor.0));
+        game.handle_message(BoardMessage::MoveToLocation(neighbor));
+
+        game.state = BoardState::TileEffect;
+        game.handle_message(BoardMessage::TogglePickaxe);
+        assert!(!game.selected_move.unwrap().pickaxe);
+
+        game.state = BoardState::Movement;
+        game.handle_message(BoardMessage::TogglePickaxe);
+        assert!(game.selected_move.unwrap().pickaxe);
+    }
+
+    #[test]
+    fn heals_only_before_the_encounter() {
+        let mut game = game_with_dice(1, 1);
+        game.bandage = 1;
+        game.health = 2;
+
+        game.state = BoardState::DefendAttack;
+        assert!(game.handle_message(BoardMessage::UseBandage).is_empty());
+        assert_eq!(game.health, 2);
+        assert_eq!(game.bandage, 1);
+
+        game.state = BoardState::TileEffect;
+        game.handle_message(BoardMessage::UseBandage);
+        assert_eq!(game.health, 2 + BANDAGE_HEAL);
+        assert_eq!(game.bandage, 0);
+    }
+
+    #[test]
+    fn changing_state_clears_the_selected_move() {
+        let mut game = game_with_dice(3, 2);
+        assign_dice(&mut game);
+        game.handle_message(BoardMessage::NextState);
+        game.handle_message(BoardMessage::NextState);
+
+        let neighbor = open_neighbor(&game);
+        game.handle_message(BoardMessage::MoveToLocation(neighbor));
+        assert!(game.selected_move.is_some());
+
+        game.handle_message(BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::TileEffect);
+        assert_eq!(game.selected_move, None);
+    }
+}
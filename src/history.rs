@@ -0,0 +1,132 @@
+//! Undo and redo of actions within a turn
+use crate::game::GameState;
+
+/// Snapshots of the game taken before each action of the current turn
+pub struct History {
+    /// States to go back to, most recent last
+    undo: Vec<GameState>,
+
+    /// States that were undone, most recent last
+    redo: Vec<GameState>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History { undo: Vec::new(), redo: Vec::new() }
+    }
+
+    /// Record that the game went from `before` to `after` because of an action.
+    ///
+    /// Actions that revealed a random value (dice, attack rolls, items) can never be
+    /// undone, otherwise the player could reroll by undoing. Since a new turn always
+    /// starts with a dice roll, this also keeps undo within the current turn. A
+    /// finished game can't be undone either.
+    pub fn record(&mut self, before: GameState, after: &GameState) {
+        if before == *after {
+            // Nothing changed, so there is nothing to undo
+            return;
+        }
+
+        self.redo.clear();
+
+        if before.rolls() != after.rolls() || after.is_over() {
+            self.undo.clear();
+            return;
+        }
+
+        self.undo.push(before);
+    }
+
+    /// Roll `game` back to before the last action. Returns false if there is nothing
+    /// to undo.
+    pub fn undo(&mut self, game: &mut GameState) -> bool {
+        match self.undo.pop() {
+            Some(previous) => {
+                self.redo.push(std::mem::replace(game, previous));
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Reapply the last undone action to `game`. Returns false if there is nothing to
+    /// redo.
+    pub fn redo(&mut self, game: &mut GameState) -> bool {
+        match self.redo.pop() {
+            Some(next) => {
+                self.undo.push(std::mem::replace(game, next));
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Returns true if there is an action to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns true if there is an undone action to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BoardMessage, BoardState};
+    use crate::rng::Rng;
+
+    /// Send `message` to `game`, recording it in `history`
+    fn send(history: &mut History, game: &mut GameState, message: BoardMessage) {
+        let before = game.clone();
+        game.handle_message(message);
+        history.record(before, game);
+    }
+
+    #[test]
+    fn undoes_and_redoes_actions() {
+        let mut history = History::new();
+        let mut game = GameState::new(Rng::from_seed(0));
+        let start = game.clone();
+
+        send(&mut history, &mut game, BoardMessage::ChooseDie1);
+        assert!(history.can_undo());
+
+        let chosen = game.clone();
+        assert!(history.undo(&mut game));
+        assert!(game == start);
+        assert!(!history.can_undo());
+
+        assert!(history.redo(&mut game));
+        assert!(game == chosen);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn ignores_actions_that_change_nothing() {
+        let mut history = History::new();
+        let mut game = GameState::new(Rng::from_seed(0));
+
+        // Nothing is selected, so there is nothing to place
+        send(&mut history, &mut game, BoardMessage::ChooseNextTile);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn never_undoes_past_a_roll() {
+        let mut history = History::new();
+        let mut game = GameState::new(Rng::from_seed(0));
+        game.state = BoardState::EndTurn;
+
+        send(&mut history, &mut game, BoardMessage::ChooseDie1);
+        assert!(history.can_undo());
+
+        // Ending the turn rolls the dice for the next one
+        send(&mut history, &mut game, BoardMessage::NextState);
+        assert_eq!(game.state, BoardState::AssignDice);
+        assert!(!history.can_undo());
+        assert!(!history.undo(&mut game));
+    }
+}
@@ -0,0 +1,119 @@
+//! Turns the mouse, keyboard and touch screen into typed events for the active screen
+use crate::*;
+
+/// Pixels the mouse can move while held and still count as a click instead of a drag
+const DRAG_THRESHOLD: f32 = 8.;
+
+/// Something the player did this frame
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Event {
+    /// The left mouse button was pressed at `press` and released at `release`
+    Click { press: (f32, f32), release: (f32, f32) },
+
+    /// The mouse moved to the given position without a button held
+    Hover((f32, f32)),
+
+    /// The mouse moved from `from` to `to` with the left button held
+    Drag { from: (f32, f32), to: (f32, f32) },
+
+    /// A key binding was pressed
+    Key(Action),
+
+    /// A gesture on the touch screen
+    Touch(Gesture),
+}
+
+impl Event {
+    /// Returns where the press and release of a click or a tap were
+    pub fn click(&self) -> Option<((f32, f32), (f32, f32))> {
+        match *self {
+            Event::Click { press, release } => Some((press, release)),
+            Event::Touch(Gesture::Tap { press, release }) => Some((press, release)),
+            _ => None
+        }
+    }
+}
+
+/// State of the input devices carried between frames
+pub struct Input {
+    touch: TouchTracker,
+
+    /// Where the left mouse button went down, while it is held
+    press: Option<(f32, f32)>,
+
+    /// Set once the held mouse moved far enough to be a drag
+    dragging: bool,
+
+    /// Mouse position on the last frame
+    last_mouse: (f32, f32),
+
+    /// Position of the last click or tap
+    pub last_click: Option<(f32, f32)>,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Input { touch: TouchTracker::new(), press: None, dragging: false,
+                last_mouse: mouse_position(), last_click: None }
+    }
+
+    /// Returns everything the player did since the last frame
+    pub fn update(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        events.extend(self.touch.update().into_iter().map(Event::Touch));
+
+        if self.touch.is_touching() {
+            // Ignore the mouse events the browser makes up for touches
+            self.press = None;
+            self.dragging = false;
+        } else {
+            self.update_mouse(&mut events);
+        }
+
+        events.extend(keys::pressed_actions().into_iter().map(Event::Key));
+
+        if let Some((_, release)) = events.iter().rev().find_map(Event::click) {
+            self.last_click = Some(release);
+        }
+
+        events
+    }
+
+    fn update_mouse(&mut self, events: &mut Vec<Event>) {
+        let pos = mouse_position();
+        let moved = pos != self.last_mouse;
+        self.last_mouse = pos;
+
+        let down = is_mouse_button_down(MouseButton::Left);
+        match (self.press, down) {
+            (None, true) => {
+                info!("{:?}", pos);
+                self.press = Some(pos);
+            }
+            (Some(press), true) => {
+                let (dx, dy) = (pos.0 - press.0, pos.1 - press.1);
+                if (dx * dx + dy * dy).sqrt() > DRAG_THRESHOLD {
+                    self.dragging = true;
+                }
+
+                if self.dragging && moved {
+                    events.push(Event::Drag { from: press, to: pos });
+                }
+            }
+            (Some(press), false) => {
+                if !self.dragging {
+                    events.push(Event::Click { press, release: pos });
+                }
+
+                self.press = None;
+                self.dragging = false;
+            }
+            (None, false) => {
+                if moved {
+                    events.push(Event::Hover(pos));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Keyboard controls. Every binding lives in `BINDINGS` so they can be found and
+//! changed in one place.
+use crate::*;
+
+/// Something a key can do
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Select the first die
+    SelectDie1,
+
+    /// Select the second die
+    SelectDie2,
+
+    /// Place the selected die on the Next Tile
+    AssignNextTile,
+
+    /// Place the selected die on the Encounter
+    AssignEncounter,
+
+    /// Rotate the walls being drawn
+    RotateWalls,
+
+    /// Move to the neighbor on the given side
+    Move(Wall),
+
+    /// Select the next teleport
+    CycleTeleport,
+
+    /// Fire the shotgun at an attacking beast
+    UseShotgun,
+
+    /// Break the selected wall with the pickaxe, or stop using it
+    TogglePickaxe,
+
+    /// Reroll the selected die with the charm
+    UseCharm,
+
+    /// Heal with the bandage
+    UseBandage,
+
+    /// Heal with the elixir
+    UseElixir,
+
+    /// Go on to the next state of the turn
+    NextState,
+
+    /// Undo the last action
+    Undo,
+
+    /// Redo the last undone action
+    Redo,
+
+    /// Switch between the rules and the board
+    ToggleRules,
+
+    /// Play or pause a replay
+    ToggleReplay,
+
+    /// Play the next step of a replay
+    StepReplay,
+
+    /// Show or hide the debug overlay
+    ToggleDebug,
+
+    /// Switch between the board image and each vector theme
+    CycleTheme,
+
+    /// Turn the animations on or off
+    ToggleAnimations,
+
+    /// Mute or unmute the sound effects
+    ToggleMute,
+
+    /// Turn the sound effects up
+    VolumeUp,
+
+    /// Turn the sound effects down
+    VolumeDown,
+
+    /// Mute or unmute the music
+    ToggleMusic,
+
+    /// Turn the music up
+    MusicUp,
+
+    /// Turn the music down
+    MusicDown,
+}
+
+/// A key, the modifiers that must be held with it, and what it does
+pub struct Binding {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub action: Action,
+}
+
+const fn bind(key: KeyCode, action: Action) -> Binding {
+    Binding { key, ctrl: false, shift: false, action }
+}
+
+const fn bind_shift(key: KeyCode, action: Action) -> Binding {
+    Binding { key, ctrl: false, shift: true, action }
+}
+
+const fn bind_ctrl(key: KeyCode, shift: bool, action: Action) -> Binding {
+    Binding { key, ctrl: true, shift, action }
+}
+
+/// Every key binding. A key may appear more than once, for example to do something
+/// different while watching a replay.
+pub const BINDINGS: &[Binding] = &[
+    bind(KeyCode::Key1,  Action::SelectDie1),
+    bind(KeyCode::Key2,  Action::SelectDie2),
+    bind(KeyCode::N,     Action::AssignNextTile),
+    bind(KeyCode::E,     Action::AssignEncounter),
+    bind(KeyCode::R,     Action::RotateWalls),
+    bind(KeyCode::Up,    Action::Move(Wall::Top)),
+    bind(KeyCode::Down,  Action::Move(Wall::Bottom)),
+    bind(KeyCode::Left,  Action::Move(Wall::Left)),
+    bind(KeyCode::Right, Action::Move(Wall::Right)),
+    bind(KeyCode::T,     Action::CycleTeleport),
+    bind(KeyCode::S,     Action::UseShotgun),
+    bind(KeyCode::P,     Action::TogglePickaxe),
+    bind(KeyCode::C,     Action::UseCharm),
+    bind(KeyCode::B,     Action::UseBandage),
+    bind(KeyCode::X,     Action::UseElixir),
+    bind(KeyCode::Space, Action::NextState),
+    bind(KeyCode::Enter, Action::NextState),
+    bind(KeyCode::Tab,   Action::ToggleRules),
+    bind_ctrl(KeyCode::Z, false, Action::Undo),
+    bind_ctrl(KeyCode::Y, false, Action::Redo),
+    bind_ctrl(KeyCode::Z, true,  Action::Redo),
+    bind(KeyCode::Space, Action::ToggleReplay),
+    bind(KeyCode::Right, Action::StepReplay),
+    bind(KeyCode::F3,    Action::ToggleDebug),
+    bind(KeyCode::F4,    Action::CycleTheme),
+    bind(KeyCode::F5,    Action::ToggleAnimations),
+    bind(KeyCode::M,     Action::ToggleMute),
+    bind(KeyCode::Equal, Action::VolumeUp),
+    bind(KeyCode::Minus, Action::VolumeDown),
+    bind_shift(KeyCode::M,     Action::ToggleMusic),
+    bind_shift(KeyCode::Equal, Action::MusicUp),
+    bind_shift(KeyCode::Minus, Action::MusicDown),
+];
+
+/// Returns the actions of every binding pressed this frame
+pub fn pressed_actions() -> Vec<Action> {
+    let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+    let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+
+    BINDINGS.iter()
+        .filter(|binding| binding.ctrl == ctrl && binding.shift == shift)
+        .filter(|binding| is_key_pressed(binding.key))
+        .map(|binding| binding.action)
+        .collect()
+}
@@ -0,0 +1,45 @@
+//! Fitting the board onto windows of any shape. Every `Button` is placed in fractions of
+//! the board, and the board is scaled as large as fits without stretching, centered with
+//! black bars along the sides that don't fit. Input goes through the same transform so
+//! what is clicked is always what is drawn.
+use crate::*;
+
+/// Width over height of `board.png`, the shape everything is laid out on
+pub const ASPECT_RATIO: f32 = 1654. / 1166.;
+
+/// Returns the area of the screen the board is drawn in, as (x, y, w, h) in pixels
+pub fn viewport() -> (f32, f32, f32, f32) {
+    let (screen_w, screen_h) = (screen_width(), screen_height());
+
+    if screen_w / screen_h > ASPECT_RATIO {
+        // Wider than the board, so bars on the left and right
+        let w = screen_h * ASPECT_RATIO;
+        ((screen_w - w) / 2., 0., w, screen_h)
+    } else {
+        // Taller than the board, so bars above and below
+        let h = screen_w / ASPECT_RATIO;
+        (0., (screen_h - h) / 2., screen_w, h)
+    }
+}
+
+/// Converts a position in fractions of the board to pixels on the screen
+pub fn to_screen(pos: (f32, f32)) -> (f32, f32) {
+    let (x, y, w, h) = viewport();
+    (x + pos.0 * w, y + pos.1 * h)
+}
+
+/// Converts a position on the screen, such as the mouse, to fractions of the board.
+/// Positions on the bars fall outside of [0, 1].
+pub fn to_board(pos: (f32, f32)) -> (f32, f32) {
+    let (x, y, w, h) = viewport();
+    ((pos.0 - x) / w, (pos.1 - y) / h)
+}
+
+/// Draw `texture` stretched over the whole board area
+pub fn draw_board_texture(texture: Texture2D) {
+    let (x, y, w, h) = viewport();
+    draw_texture_ex(texture, x, y, WHITE, DrawTextureParams {
+        dest_size: Some(vec2(w, h)),
+        ..Default::default()
+    });
+}
@@ -0,0 +1,9 @@
+//! Everything about Temple Antics that runs without a window: the rules engine, the
+//! temple and encounter data, and the tools built on top of them.
+pub mod game;
+pub mod map;
+pub mod policy;
+pub mod rng;
+pub mod sim;
+pub mod solver;
+pub mod tables;
@@ -1,18 +1,51 @@
-#![feature(vec_remove_item)]
-#![feature(or_patterns)]
-use macroquad::*;
+use macroquad::prelude::*;
 
 mod button;
-use button::Button;
+use button::{draw_tooltip, Button};
 
 mod traits;
 use traits::*;
 
 mod board;
-use board::Board;
+use board::{Board, BoardCommand};
 
-mod rng;
+use templeantics::{game, rng, solver, tables};
+use templeantics::policy::{GreedyPolicy, Policy, RandomPolicy};
+use game::*;
 use rng::Rng;
+use tables::Difficulty;
+
+mod history;
+use history::History;
+
+mod save;
+
+mod replay;
+use replay::{Replay, ReplayPlayer};
+
+mod keys;
+use keys::Action;
+
+mod touch;
+use touch::{Gesture, TouchTracker};
+
+mod input;
+use input::{Event, Input};
+
+mod layout;
+
+mod vector;
+use vector::Theme;
+
+mod text;
+
+mod animation;
+use animation::Animations;
+
+mod storage;
+
+mod audio;
+use audio::Audio;
 
 struct Rules {
     texture: Texture2D
@@ -39,94 +72,267 @@ pub fn color_from_usize(r: usize, g: usize, b: usize, a: usize) -> Color {
 
 const WALL_WIDTH: f32 = 0.010;
 
+/// Load one of the bundled images. Nothing can be drawn without them.
+async fn load_image(path: &str) -> Texture2D {
+    load_texture(path).await.unwrap_or_else(|e| panic!("Failed to load {}: {:?}", path, e))
+}
+
 #[macroquad::main("TempleAntics")]
 async fn main() {
     // Current state state
     let mut state = State::Board;
 
     // Various board states
-    let board_tex: Texture2D = load_texture("./static/board.png").await;
+    let board_tex: Texture2D = load_image("./static/board.png").await;
     let dice_textures = [
-        load_texture("./static/die_1.png").await, // 0th die texture does not matter
-        load_texture("./static/die_1.png").await,
-        load_texture("./static/die_2.png").await,
-        load_texture("./static/die_3.png").await,
-        load_texture("./static/die_4.png").await,
-        load_texture("./static/die_5.png").await,
-        load_texture("./static/die_6.png").await,
+        load_image("./static/die_1.png").await, // 0th die texture does not matter
+        load_image("./static/die_1.png").await,
+        load_image("./static/die_2.png").await,
+        load_image("./static/die_3.png").await,
+        load_image("./static/die_4.png").await,
+        load_image("./static/die_5.png").await,
+        load_image("./static/die_6.png").await,
     ];
 
-    let mut board = Board::new(board_tex, dice_textures);
+    // Watch a recorded game with `--replay <file>`, pick the difficulty of a new game
+    // with `--difficulty <name>`, set how many turns the hints look ahead with
+    // `--hint-depth <turns>`, or replay a specific game by giving its seed
+    let mut replay = None;
+    let mut difficulty: Option<Difficulty> = None;
+    let mut seed = None;
+    let mut hint_depth = board::DEFAULT_HINT_DEPTH;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--replay" => match Replay::read(&args.next().unwrap_or_default()) {
+                Ok(found) => replay = Some(ReplayPlayer::new(found)),
+                Err(e) => info!("{}", e),
+            },
+            "--difficulty" => {
+                let name = args.next().unwrap_or_default();
+                match tables::find_difficulty(&tables::bundled_difficulties(), &name) {
+                    Ok(found) => difficulty = Some(found),
+                    Err(e) => info!("{}", e),
+                }
+            }
+            "--hint-depth" => match args.next().unwrap_or_default().parse::<usize>() {
+                Ok(depth) if depth > 0 => hint_depth = depth,
+                _ => info!("--hint-depth needs a number of turns of at least 1"),
+            },
+            _ => match arg.parse::<u64>() {
+                Ok(found) => seed = Some(found),
+                Err(_) => info!("Unknown argument {}, expected --replay <file>, \
+                                 --difficulty <name>, --hint-depth <turns> or a seed", arg),
+            },
+        }
+    }
+
+    // Otherwise resume the saved game, if there is one
+    let saved = match save::load() {
+        Ok(game) => game,
+        Err(e) => {
+            info!("{}", e);
+            None
+        }
+    };
+
+    let mut board = match (&replay, seed, difficulty, saved) {
+        (Some(replay), ..) => {
+            // Watching a replay must not overwrite the player's own game
+            let mut board = Board::from_game(board_tex, dice_textures,
+                                             replay.start_game());
+            board.set_autosave(false);
+            board
+        }
+        (None, None, None, Some(game)) => Board::from_game(board_tex, dice_textures, game),
+        (None, seed, difficulty, _) => {
+            let rng = seed.map_or_else(Rng::new, Rng::from_seed);
+            let difficulty = difficulty
+                .unwrap_or_else(|| tables::bundled_difficulties().remove(0));
+            Board::new(board_tex, dice_textures, rng, difficulty)
+        }
+    };
+    board.set_hint_depth(hint_depth);
 
-    let rules_tex: Texture2D = load_texture("./static/rules.png").await;
+    // Controls for watching a replay
+    let mut play_button = Button::new_with_text(0.6, 0.95, 0.09, 0.04,
+        ("Play".to_string(), RED));
+    let step_button = Button::new_with_text(0.7, 0.95, 0.09, 0.04,
+        ("Step".to_string(), RED));
+
+    // Set when the replay no longer matches the rules
+    let mut replay_error: Option<String> = None;
+
+    text::load_font().await;
+    board.set_audio(Audio::load().await);
+
+    let rules_tex: Texture2D = load_image("./static/rules.png").await;
     let rules = Rules::new(rules_tex);
 
     let mut rules_button = Button { x: 0.5, y: 0.95, w: 0.09, h: 0.04, 
         text: Some(("To Board".to_string(), RED)), texture: None, 
-        background_color: Some(BLACK) };
-
-    let mut rng = Rng::new();
-
-    let mut rng_count = 0;
+        background_color: Some(BLACK), fit: std::cell::Cell::new(None) };
 
     // 0.0312407 0.57166123 0.051741533 0.06194806
     // 0.089999534 0.57197994 0.055775665 0.06312579
     // 0.07401181 0.52855253 0.07110771 0.035393357
 
-    let mut mouse_repeat = false;
-    let mut mouse_click  = false;
-    let mut last_click   = (0., 0.);
+    let mut input = Input::new();
+
+    // Show the clickable regions and the last click
+    let mut debug = false;
+
+    // Tooltip shown while hovering or after a long press, and where
+    let mut tooltip: Option<(String, (f32, f32))> = None;
 
     loop {
-        /* Naive mouse click event */
-        mouse_click = false;
-        if is_mouse_button_down(MouseButton::Left) && !mouse_repeat {
-            info!("{:?}", mouse_position());
-            mouse_repeat = true;
-        }
-        if !is_mouse_button_down(MouseButton::Left) && mouse_repeat {
-            last_click = mouse_position();
-            mouse_click = true;
-            mouse_repeat = false;
-        }
+        for event in input.update() {
+            // Browsers block sound until the player interacts with the page
+            if !matches!(event, Event::Hover(_)) {
+                board.start_music();
+            }
+
+            // Clicks and taps only count if they are pressed and released on the same
+            // target
+            if let Some((press, release)) = event.click() {
+                tooltip = None;
 
-        if mouse_click {
+                if rules_button.contains(press) && rules_button.contains(release) {
+                    info!("Click rules button");
+                    state = match state {
+                        State::Rules => State::Board,
+                        State::Board => State::Rules,
+                    };
+                    continue;
+                }
+
+                if let Some(replay) = &mut replay {
+                    if play_button.contains(press) && play_button.contains(release) {
+                        replay.toggle_play();
+                    }
 
-            if rules_button.contains(last_click) {
-                info!("Click rules button");
-                state = match state {
-                    State::Rules => State::Board,
-                    State::Board => State::Rules,
-                };
+                    if step_button.contains(press) && step_button.contains(release) {
+                        if let Err(e) = replay.step(&mut board) {
+                            replay_error = Some(e);
+                        }
+                    }
+
+                    continue;
+                }
 
+                // The rules are only a picture, so only the board takes clicks
+                if let State::Board = state {
+                    if let Some(message) = board.handle_press_release(press, release) {
+                        board.handle_message(message);
+                    }
+                }
+
+                continue;
             }
 
-            if matches!(state, State::Board) {
-                if let Some(message) = board.handle_click(last_click) {
-                    board.handle_message(message);
+            match (event, &mut replay) {
+                (Event::Hover(pos), _) | (Event::Touch(Gesture::LongPress(pos)), _) => {
+                    let text = match state {
+                        State::Board => board.tooltip(pos),
+                        State::Rules => None,
+                    };
+                    tooltip = text.map(|text| (text, pos));
+                }
+                (Event::Key(Action::ToggleRules), _) => {
+                    state = match state {
+                        State::Rules => State::Board,
+                        State::Board => State::Rules,
+                    };
+                }
+                (Event::Key(Action::ToggleDebug), _) => debug = !debug,
+                (Event::Key(Action::CycleTheme), _) => board.cycle_theme(),
+                (Event::Key(Action::ToggleAnimations), _) => board.toggle_animations(),
+                (Event::Key(Action::ToggleMute), _) => board.toggle_mute(),
+                (Event::Key(Action::VolumeUp), _) => board.change_volume(audio::VOLUME_STEP),
+                (Event::Key(Action::VolumeDown), _) => {
+                    board.change_volume(-audio::VOLUME_STEP)
+                }
+                (Event::Key(Action::ToggleMusic), _) => board.toggle_music_mute(),
+                (Event::Key(Action::MusicUp), _) => {
+                    board.change_music_volume(audio::VOLUME_STEP)
+                }
+                (Event::Key(Action::MusicDown), _) => {
+                    board.change_music_volume(-audio::VOLUME_STEP)
+                }
+                (Event::Key(Action::ToggleReplay), Some(replay)) => replay.toggle_play(),
+                (Event::Key(Action::StepReplay), Some(replay)) => {
+                    if let Err(e) = replay.step(&mut board) {
+                        replay_error = Some(e);
+                    }
                 }
+                (Event::Key(action), None) => {
+                    if let State::Board = state {
+                        if let Some(message) = board.handle_key(action) {
+                            board.handle_message(message);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(replay) = &mut replay {
+            if let Err(e) = replay.update(&mut board, get_frame_time()) {
+                replay_error = Some(e);
             }
         }
 
+        board.update(get_frame_time());
+
+        // Clear the bars around the board
+        clear_background(BLACK);
+
         // Draw the current state
         match state {
             State::Board => {
                 board.draw();
-                // board.debug_draw();
+                if debug {
+                    board.debug_draw();
+                }
                 rules_button.change_text("To Rules".to_string());
             }
             State::Rules => {
                 rules.draw();
-                rules.debug_draw();
+                if debug {
+                    rules.debug_draw();
+                }
                 rules_button.change_text("To Board".to_string());
             }
         }
 
-        draw_circle(last_click.0, last_click.1, 10., GREEN);
+        if debug {
+            if let Some((x, y)) = input.last_click {
+                draw_circle(x, y, 10., GREEN);
+            }
+        }
 
         // Draw the switch board/rules button
         rules_button.draw();
 
+        if let Some((text, pos)) = &tooltip {
+            draw_tooltip(text, *pos);
+        }
+
+        if let Some(replay) = &replay {
+            let label = if replay.is_playing() { "Pause" } else { "Play" };
+            play_button.change_text(label.to_string());
+            play_button.draw();
+            step_button.draw();
+
+            let (played, total) = replay.progress();
+            let status = match &replay_error {
+                Some(e) => e.clone(),
+                None => format!("Replay step {}/{}", played, total),
+            };
+            let (board_x, board_y, _, board_h) = layout::viewport();
+            text::draw(&status, board_x + 5., board_y + board_h - 40., 20., BLACK);
+        }
+
         next_frame().await
     }
 }
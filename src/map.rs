@@ -0,0 +1,270 @@
+//! Layout of a temple, loaded from `static/temple.ron` so alternate temples can be
+//! shipped as data.
+use crate::game::{Item, Wall, WallKey};
+use serde::{Deserialize, Serialize};
+
+/// A temple layout as it is written in the map file, with tiles as (x, y) coordinates
+#[derive(Debug, Deserialize)]
+struct MapFile {
+    name: String,
+    width: usize,
+    height: usize,
+    turns: usize,
+    start: (usize, usize),
+    exits: Vec<(usize, usize)>,
+    teleports: Vec<(usize, usize)>,
+    items: Vec<((usize, usize), Item)>,
+}
+
+/// A temple layout. Tiles are indexes into the grid, laid out row by row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Map {
+    /// Name of the temple
+    pub name: String,
+
+    /// Width of the grid in tiles
+    pub width: usize,
+
+    /// Height of the grid in tiles
+    pub height: usize,
+
+    /// Number of turns on the turn track. The game is lost if the track runs out.
+    pub turns: usize,
+
+    /// Tile the player starts the game on
+    pub start: usize,
+
+    /// Tiles that escape the temple when reached with the idol
+    pub exits: Vec<usize>,
+
+    /// Tiles that allow the player to teleport to each other
+    pub teleports: Vec<usize>,
+
+    /// Tiles which hand out an item when the player's tile effect is checked
+    pub items: Vec<(usize, Item)>,
+}
+
+impl Map {
+    /// Parse and validate a map from RON
+    pub fn parse(input: &str) -> Result<Map, String> {
+        let file: MapFile = ron::de::from_str(input)
+            .map_err(|err| format!("Invalid map: {}", err))?;
+
+        Map::from_file(file)
+    }
+
+    /// Validate a map file and convert its coordinates to tiles
+    fn from_file(file: MapFile) -> Result<Map, String> {
+        if file.width == 0 || file.height == 0 {
+            return Err(format!("{}: Empty grid", file.name));
+        }
+
+        if file.turns == 0 {
+            return Err(format!("{}: No turns on the turn track", file.name));
+        }
+
+        // Convert every coordinate to a tile index, checking that it is on the grid
+        let tile = |(x, y): (usize, usize)| {
+            if x < file.width && y < file.height {
+                Ok(x + file.width * y)
+            } else {
+                Err(format!("{}: ({}, {}) is off the grid", file.name, x, y))
+            }
+        };
+
+        let start = tile(file.start)?;
+        let exits = file.exits.iter().map(|&xy| tile(xy))
+            .collect::<Result<Vec<_>, _>>()?;
+        let teleports = file.teleports.iter().map(|&xy| tile(xy))
+            .collect::<Result<Vec<_>, _>>()?;
+        let items = file.items.iter().map(|&(xy, item)| Ok((tile(xy)?, item)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        // Reject maps that load fine but can't be won or played as written
+        if !items.iter().any(|&(_, item)| item == Item::Idol) {
+            return Err(format!("{}: No idol to find", file.name));
+        }
+
+        if exits.is_empty() {
+            return Err(format!("{}: No exits to escape through", file.name));
+        }
+
+        if teleports.len() == 1 {
+            return Err(format!("{}: A single teleport has nowhere to lead", file.name));
+        }
+
+        for (i, &(xy, _)) in file.items.iter().enumerate() {
+            if file.items[..i].iter().any(|&(other, _)| other == xy) {
+                return Err(format!("{}: ({}, {}) has more than one item", file.name,
+                                   xy.0, xy.1));
+            }
+        }
+
+        Ok(Map {
+            name: file.name,
+            width: file.width,
+            height: file.height,
+            turns: file.turns,
+            start,
+            exits,
+            teleports,
+            items,
+        })
+    }
+
+    /// Returns the temple bundled with the game
+    pub fn bundled() -> Map {
+        Map::parse(include_str!("../static/temple.ron"))
+            .expect("Bundled map is invalid")
+    }
+
+    /// Returns the number of tiles on the grid
+    pub fn tiles(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Returns the item handed out by the given tile, if any
+    pub fn item_at(&self, tile: usize) -> Option<Item> {
+        self.items.iter()
+            .find(|(item_tile, _)| *item_tile == tile)
+            .map(|&(_, item)| item)
+    }
+
+    /// Returns the neighbors of the given tile along with the wall between them
+    pub fn neighbors(&self, tile: usize) -> Vec<(Wall, usize)> {
+        let col = tile % self.width;
+        let row = tile / self.width;
+        let mut neighbors = Vec::new();
+
+        if col > 0 {
+            neighbors.push((Wall::Left, tile - 1));
+        }
+
+        if col + 1 < self.width {
+            neighbors.push((Wall::Right, tile + 1));
+        }
+
+        if row > 0 {
+            neighbors.push((Wall::Top, tile - self.width));
+        }
+
+        if row + 1 < self.height {
+            neighbors.push((Wall::Bottom, tile + self.width));
+        }
+
+        neighbors
+    }
+
+    /// Normalizes the given side of a tile so that a wall shared by two neighbors
+    /// always has the same key
+    pub fn wall_key(&self, tile: usize, wall: Wall) -> WallKey {
+        match wall {
+            Wall::Right if tile % self.width + 1 < self.width => (tile + 1, Wall::Left),
+            Wall::Bottom if tile / self.width + 1 < self.height =>
+                (tile + self.width, Wall::Top),
+            _ => (tile, wall)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small valid map with a cave on either side of the idol
+    const MAP: &str = r#"(
+        name: "Test",
+        width: 3,
+        height: 2,
+        turns: 5,
+        start: (0, 1),
+        exits: [(2, 1)],
+        teleports: [(0, 0), (2, 0)],
+        items: [((1, 0), Idol)],
+    )"#;
+
+    /// Returns the valid test map file
+    fn map_file() -> MapFile {
+        ron::de::from_str(MAP).unwrap()
+    }
+
+    #[test]
+    fn parses_coordinates_into_tiles() {
+        let map = Map::parse(MAP).unwrap();
+        assert_eq!(map.tiles(), 6);
+        assert_eq!(map.start, 3);
+        assert_eq!(map.exits, vec![5]);
+        assert_eq!(map.teleports, vec![0, 2]);
+        assert_eq!(map.item_at(1), Some(Item::Idol));
+    }
+
+    #[test]
+    fn rejects_an_empty_grid() {
+        let mut file = map_file();
+        file.width = 0;
+        assert_eq!(Map::from_file(file).unwrap_err(), "Test: Empty grid");
+    }
+
+    #[test]
+    fn rejects_no_turns() {
+        let mut file = map_file();
+        file.turns = 0;
+        assert_eq!(Map::from_file(file).unwrap_err(), "Test: No turns on the turn track");
+    }
+
+    #[test]
+    fn rejects_a_start_off_the_grid() {
+        let mut file = map_file();
+        file.start = (3, 0);
+        assert_eq!(Map::from_file(file).unwrap_err(), "Test: (3, 0) is off the grid");
+    }
+
+    #[test]
+    fn rejects_an_exit_off_the_grid() {
+        let mut file = map_file();
+        file.exits = vec![(0, 2)];
+        assert_eq!(Map::from_file(file).unwrap_err(), "Test: (0, 2) is off the grid");
+    }
+
+    #[test]
+    fn rejects_an_item_off_the_grid() {
+        let mut file = map_file();
+        file.items = vec![((5, 5), Item::Idol)];
+        assert_eq!(Map::from_file(file).unwrap_err(), "Test: (5, 5) is off the grid");
+    }
+
+    #[test]
+    fn rejects_a_map_without_the_idol() {
+        let mut file = map_file();
+        file.items = vec![((1, 0), Item::Elixir)];
+        assert_eq!(Map::from_file(file).unwrap_err(), "Test: No idol to find");
+    }
+
+    #[test]
+    fn rejects_a_map_without_exits() {
+        let mut file = map_file();
+        file.exits.clear();
+        assert_eq!(Map::from_file(file).unwrap_err(), "Test: No exits to escape through");
+    }
+
+    #[test]
+    fn rejects_a_single_teleport() {
+        let mut file = map_file();
+        file.teleports.pop();
+        assert_eq!(Map::from_file(file).unwrap_err(),
+                   "Test: A single teleport has nowhere to lead");
+    }
+
+    #[test]
+    fn rejects_two_items_on_one_tile() {
+        let mut file = map_file();
+        file.items.push(((1, 0), Item::Elixir));
+        assert_eq!(Map::from_file(file).unwrap_err(), "Test: (1, 0) has more than one item");
+    }
+
+    #[test]
+    fn rejects_a_malformed_map() {
+        let error = Map::parse(&MAP.replace("turns: 5", "turns: \"many\"")).unwrap_err();
+        assert!(error.starts_with("Invalid map"));
+    }
+}
@@ -0,0 +1,388 @@
+//! Policies that play the game. A policy only makes the decisions a player makes, and
+//! `Policy::plan` turns those into the same `BoardMessage`s a human would send.
+use crate::game::*;
+use crate::rng::Rng;
+
+/// A move to a neighboring tile
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Move {
+    /// Wall crossed and the tile moved to, as returned by `GameState::get_neighbors`
+    pub neighbor: (Wall, usize),
+
+    /// Break a built wall with the pickaxe instead of with health
+    pub pickaxe: bool,
+}
+
+/// Something that can play a game on its own
+pub trait Policy {
+    /// Name of the policy, as shown in reports and on the board
+    fn name(&self) -> &str;
+
+    /// Returns which die (1 or 2) goes on the Next Tile. The other die goes on the
+    /// Encounter.
+    fn assign_dice(&mut self, game: &GameState) -> usize;
+
+    /// Returns the orientation (0 to 3) to draw the walls of the Next Tile in
+    fn wall_orientation(&mut self, game: &GameState) -> usize;
+
+    /// Returns the neighbor to move to
+    fn movement(&mut self, game: &GameState) -> Move;
+
+    /// Returns the teleport to take, or None to stay on the current one
+    fn teleport(&mut self, game: &GameState) -> Option<usize>;
+
+    /// Returns true to fire the shotgun at an attacking beast
+    fn shoot(&mut self, _game: &GameState) -> bool {
+        true
+    }
+
+    /// Returns the healing item to use right now, if any
+    fn heal(&mut self, _game: &GameState) -> Option<Item> {
+        None
+    }
+
+    /// Returns the messages that make this policy's decision for the current state and
+    /// then move on to the next state
+    fn plan(&mut self, game: &GameState) -> Vec<BoardMessage> {
+        let mut messages = Vec::new();
+
+        if game.is_over() {
+            return messages;
+        }
+
+        // Healing items only work before the encounter
+        let heal = if game.can_heal() { self.heal(game) } else { None };
+        match heal {
+            Some(Item::Bandage) if game.bandage > 0 => {
+                messages.push(BoardMessage::UseBandage);
+            }
+            Some(Item::Elixir) if game.elixir => messages.push(BoardMessage::UseElixir),
+            _ => {}
+        }
+
+        match game.state {
+            BoardState::AssignDice => {
+                let choose = |die| if die == 1 {
+                    BoardMessage::ChooseDie1
+                } else {
+                    BoardMessage::ChooseDie2
+                };
+
+                match (game.die1, game.die2) {
+                    (Some(_), Some(_)) => {
+                        let next_tile_die = self.assign_dice(game);
+                        messages.push(choose(next_tile_die));
+                        messages.push(BoardMessage::ChooseNextTile);
+                        messages.push(choose(3 - next_tile_die));
+                        messages.push(BoardMessage::ChooseEncounter);
+                    }
+                    (Some(_), None) | (None, Some(_)) => {
+                        // Part of the dice were assigned already, so the rest goes in
+                        // the empty space
+                        messages.push(choose(if game.die1.is_some() { 1 } else { 2 }));
+                        messages.push(if game.next_tile.is_none() {
+                            BoardMessage::ChooseNextTile
+                        } else {
+                            BoardMessage::ChooseEncounter
+                        });
+                    }
+                    (None, None) => {}
+                }
+            }
+            BoardState::DrawWalls | BoardState::ShortcutDrawWalls => {
+                let orientation = self.wall_orientation(game) % 4;
+                let turns = (orientation + 4 - game.wall_orientation % 4) % 4;
+                for _ in 0..turns {
+                    messages.push(BoardMessage::ChangeWallOrientation);
+                }
+            }
+            BoardState::Movement | BoardState::ShortcutMovement => {
+                let Move { neighbor, pickaxe } = self.movement(game);
+                messages.push(BoardMessage::MoveToLocation(neighbor));
+                if pickaxe && game.pickaxe > 0 && game.is_wall_built(neighbor.0) {
+                    messages.push(BoardMessage::TogglePickaxe);
+                }
+            }
+            BoardState::ChooseTeleport | BoardState::ShortcutChooseTeleport => {
+                if let Some(teleport) = self.teleport(game) {
+                    messages.push(BoardMessage::ChooseTeleport(teleport));
+                }
+            }
+            BoardState::DefendAttack if game.shotgun > 0 && self.shoot(game) => {
+                messages.push(BoardMessage::UseShotgun);
+                return messages;
+            }
+            _ => {}
+        }
+
+        messages.push(BoardMessage::NextState);
+        messages
+    }
+}
+
+/// Plays by picking uniformly between the legal choices
+pub struct RandomPolicy {
+    rng: Rng,
+}
+
+impl RandomPolicy {
+    pub fn new(rng: Rng) -> Self {
+        RandomPolicy { rng }
+    }
+
+    /// Returns true with a one in `n` chance
+    fn one_in(&mut self, n: u64) -> bool {
+        self.rng.next_u64().is_multiple_of(n)
+    }
+}
+
+impl Policy for RandomPolicy {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn assign_dice(&mut self, _game: &GameState) -> usize {
+        if self.one_in(2) { 1 } else { 2 }
+    }
+
+    fn wall_orientation(&mut self, _game: &GameState) -> usize {
+        (self.rng.next_u64() % 4) as usize
+    }
+
+    fn movement(&mut self, game: &GameState) -> Move {
+        let neighbors = game.get_neighbors();
+        let neighbor = neighbors[self.rng.next_u64() as usize % neighbors.len()];
+        Move { neighbor, pickaxe: self.one_in(2) }
+    }
+
+    fn teleport(&mut self, game: &GameState) -> Option<usize> {
+        // Either stay, or take one of the teleports
+        let teleports = &game.map.teleports;
+        let index = self.rng.next_u64() as usize % (teleports.len() + 1);
+        teleports.get(index).copied()
+    }
+
+    fn shoot(&mut self, _game: &GameState) -> bool {
+        self.one_in(2)
+    }
+
+    fn heal(&mut self, game: &GameState) -> Option<Item> {
+        if game.health == MAX_HEALTH || !self.one_in(4) {
+            return None;
+        }
+
+        if self.one_in(2) { Some(Item::Bandage) } else { Some(Item::Elixir) }
+    }
+}
+
+/// Cost of an unreachable tile
+const UNREACHABLE: usize = usize::MAX / 2;
+
+/// Walks the cheapest path to the idol and then to the nearest exit, drawing walls
+/// where they are least in the way and avoiding the most dangerous encounters
+pub struct GreedyPolicy;
+
+impl GreedyPolicy {
+    /// Returns the tiles the player is heading to
+    fn targets(game: &GameState) -> Vec<usize> {
+        if game.idol {
+            return game.map.exits.clone();
+        }
+
+        game.map.items.iter()
+            .filter(|&&(_, item)| item == Item::Idol)
+            .map(|&(tile, _)| tile)
+            .collect()
+    }
+
+    /// Returns the cost of stepping from `tile` through `wall`, given the `built` walls.
+    /// Breaking a wall costs the health it takes, or a step more with the pickaxe.
+    fn step_cost(game: &GameState, built: &[WallKey], tile: usize, wall: Wall) -> usize {
+        if !built.contains(&game.map.wall_key(tile, wall)) {
+            1
+        } else if game.pickaxe > 0 {
+            2
+        } else {
+            1 + WALL_BREAK_COST
+        }
+    }
+
+    /// Returns the cheapest cost from every tile to one of the targets, with `built`
+    /// walls in the way. Teleports link to each other for free when `teleports` is set.
+    fn costs(game: &GameState, built: &[WallKey], teleports: bool) -> Vec<usize> {
+        let map = &game.map;
+        let mut costs = vec![UNREACHABLE; map.tiles()];
+        let mut done = vec![false; map.tiles()];
+
+        for target in GreedyPolicy::targets(game) {
+            costs[target] = 0;
+        }
+
+        // Dijkstra over the grid, which is small enough to not need a heap. Walls are
+        // the same from both sides, so costs from the targets are costs to the targets.
+        while let Some(tile) = (0..map.tiles())
+                .filter(|&tile| !done[tile] && costs[tile] < UNREACHABLE)
+                .min_by_key(|&tile| costs[tile]) {
+            done[tile] = true;
+
+            let mut next = map.neighbors(tile).into_iter()
+                .map(|(wall, neighbor)| {
+                    (neighbor, costs[tile] + GreedyPolicy::step_cost(game, built, tile, wall))
+                })
+                .collect::<Vec<_>>();
+
+            if teleports && map.teleports.contains(&tile) {
+                next.extend(map.teleports.iter().map(|&teleport| (teleport, costs[tile])));
+            }
+
+            for (neighbor, cost) in next {
+                if cost < costs[neighbor] {
+                    costs[neighbor] = cost;
+                }
+            }
+        }
+
+        costs
+    }
+
+    /// Returns the best move from the current location with the `built` walls, along
+    /// with its cost
+    fn best_move(game: &GameState, built: &[WallKey]) -> (Move, usize) {
+        let costs = GreedyPolicy::costs(game, built, true);
+        let location = game.player_location;
+
+        game.get_neighbors().into_iter()
+            .map(|(wall, neighbor)| {
+                let wall_built = built.contains(&game.map.wall_key(location, wall));
+                let mut cost = costs[neighbor]
+                    .saturating_add(GreedyPolicy::step_cost(game, built, location, wall));
+
+                // Never break a wall with the last of our health
+                if wall_built && game.pickaxe == 0 && game.health <= WALL_BREAK_COST {
+                    cost = cost.saturating_add(UNREACHABLE);
+                }
+
+                let pickaxe = wall_built && game.pickaxe > 0;
+                (Move { neighbor: (wall, neighbor), pickaxe }, cost)
+            })
+            .min_by_key(|&(_, cost)| cost)
+            .expect("Every tile has a neighbor")
+    }
+
+    /// Returns the best wall orientation for the given Next Tile face, along with the
+    /// cost of the best move once those walls are drawn
+    fn best_orientation(game: &GameState, next_tile: usize) -> (usize, usize) {
+        (0..4)
+            .map(|orientation| {
+                let mut built = game.built_walls.clone();
+                built.extend(walls_for_tile(next_tile, orientation).iter()
+                    .map(|&wall| game.map.wall_key(game.player_location, wall)));
+
+                (orientation, GreedyPolicy::best_move(game, &built).1)
+            })
+            .min_by_key(|&(_, cost)| cost)
+            .unwrap()
+    }
+
+    /// Returns roughly how much health the encounter with the given face costs. Negative
+    /// values are encounters that help.
+    fn encounter_loss(game: &GameState, face: usize) -> f64 {
+        let difficulty = &game.difficulty;
+        let turn = game.current_turn;
+
+        // Damage left after the machete and the shotgun, averaged over the attack die
+        let attack = |table| {
+            (1..=6)
+                .map(|roll| {
+                    let mut damage = difficulty.attack_damage(table, roll, turn);
+                    if game.machete {
+                        damage = damage.saturating_sub(MACHETE_ATTACK);
+                    }
+                    if game.shotgun > 0 {
+                        damage = damage.saturating_sub(SHOTGUN_ATTACK);
+                    }
+                    damage as f64
+                })
+                .sum::<f64>() / 6.
+        };
+
+        match face {
+            1 => attack(&difficulty.sneak_beast),
+            2 => -(difficulty.campfire_heal.min(MAX_HEALTH - game.health) as f64),
+            3 => attack(&difficulty.beast_attack),
+            4 => -1.,
+            5 => -0.5,
+            _ => {
+                let damage = difficulty.trap_damage(turn);
+                let absorbed = if game.machete { MACHETE_ATTACK } else { 0 };
+                damage.saturating_sub(absorbed) as f64
+            }
+        }
+    }
+}
+
+impl Policy for GreedyPolicy {
+    fn name(&self) -> &str {
+        "greedy"
+    }
+
+    fn assign_dice(&mut self, game: &GameState) -> usize {
+        let (die1, die2) = match (game.die1, game.die2) {
+            (Some(die1), Some(die2)) => (die1, die2),
+            _ => return 1
+        };
+
+        // A 6 on the Next Tile is rerolled, so it costs the average of the other faces
+        let move_cost = |next_tile| {
+            if next_tile == 6 {
+                (1..=5).map(|face| GreedyPolicy::best_orientation(game, face).1 as f64)
+                    .sum::<f64>() / 5.
+            } else {
+                GreedyPolicy::best_orientation(game, next_tile).1 as f64
+            }
+        };
+
+        let score = |next_tile, encounter| {
+            move_cost(next_tile) + GreedyPolicy::encounter_loss(game, encounter)
+        };
+
+        if score(die1, die2) <= score(die2, die1) { 1 } else { 2 }
+    }
+
+    fn wall_orientation(&mut self, game: &GameState) -> usize {
+        match game.next_tile {
+            Some(next_tile) if next_tile != 6 => {
+                GreedyPolicy::best_orientation(game, next_tile).0
+            }
+            _ => game.wall_orientation
+        }
+    }
+
+    fn movement(&mut self, game: &GameState) -> Move {
+        GreedyPolicy::best_move(game, &game.built_walls).0
+    }
+
+    fn teleport(&mut self, game: &GameState) -> Option<usize> {
+        // Only teleport if it gets us closer walking from where we land
+        let costs = GreedyPolicy::costs(game, &game.built_walls, false);
+        let best = game.map.teleports.iter()
+            .copied()
+            .min_by_key(|&teleport| costs[teleport])?;
+
+        if costs[best] < costs[game.player_location] { Some(best) } else { None }
+    }
+
+    fn heal(&mut self, game: &GameState) -> Option<Item> {
+        if game.health > 2 {
+            return None;
+        }
+
+        if game.bandage > 0 {
+            Some(Item::Bandage)
+        } else if game.elixir {
+            Some(Item::Elixir)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Recording and playing back games. A replay is the seed plus every message the board
+//! handled, along with the dice rolled while handling it so a replay that no longer
+//! matches the rules can be spotted at the exact step it diverges.
+use crate::*;
+use serde::{Deserialize, Serialize};
+
+/// Version of the replay format
+pub const REPLAY_VERSION: u32 = 2;
+
+/// Seconds between steps while a replay is playing
+const STEP_DELAY: f32 = 0.5;
+
+/// Every action taken in a single game
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    version: u32,
+
+    /// Seed of the game
+    seed: u64,
+
+    /// State the game started from, if it isn't simply a new game from `seed` in the
+    /// bundled temple, such as a game resumed from a save
+    start: Option<GameState>,
+
+    /// Each message that changed the game, with the faces of the dice rolled while
+    /// handling it
+    steps: Vec<(BoardMessage, Vec<u8>)>,
+
+    /// Steps taken back by undo, most recent last, until something new is recorded
+    #[serde(skip)]
+    undone: Vec<(BoardMessage, Vec<u8>)>,
+}
+
+impl Replay {
+    /// Start recording a replay of `game`
+    pub fn new(game: &GameState) -> Self {
+        let fresh = *game == GameState::new(Rng::from_seed(game.seed()));
+        Replay {
+            version: REPLAY_VERSION,
+            seed: game.seed(),
+            start: if fresh { None } else { Some(game.clone()) },
+            steps: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// Returns the game as it was before the first step
+    pub fn start_game(&self) -> GameState {
+        match &self.start {
+            Some(game) => game.clone(),
+            None => GameState::new(Rng::from_seed(self.seed))
+        }
+    }
+
+    /// Record that `message` was handled, going from a game with the `before` rng to
+    /// `after`
+    pub fn record(&mut self, message: BoardMessage, before: Rng, after: &GameState) {
+        self.steps.push((message, rolls_between(before, after)));
+        self.undone.clear();
+    }
+
+    /// Drop the last step, since the game went back to before it
+    pub fn undo(&mut self) {
+        if let Some(step) = self.steps.pop() {
+            self.undone.push(step);
+        }
+    }
+
+    /// Restore the last step dropped by `undo`
+    pub fn redo(&mut self) {
+        if let Some(step) = self.undone.pop() {
+            self.steps.push(step);
+        }
+    }
+
+    /// Serialize the replay into its compact text form
+    pub fn to_string(&self) -> Result<String, String> {
+        ron::ser::to_string(self).map_err(|e| format!("Failed to serialize replay: {}", e))
+    }
+
+    /// Parse a replay written by `to_string`
+    pub fn from_str(data: &str) -> Result<Replay, String> {
+        let replay: Replay = ron::de::from_str(data)
+            .map_err(|e| format!("Failed to parse replay: {}", e))?;
+
+        if replay.version != REPLAY_VERSION {
+            return Err(format!("Replay is version {}, expected {}", replay.version,
+                               REPLAY_VERSION));
+        }
+
+        Ok(replay)
+    }
+
+    /// Name the replay of this game is stored under. A game that didn't start fresh,
+    /// such as one resumed from a save, is named after the turn it started on so its
+    /// replay never replaces the one of the whole game.
+    pub fn name(&self) -> String {
+        match &self.start {
+            Some(game) => format!("{}.turn{}.replay", self.seed, game.current_turn),
+            None => format!("{}.replay", self.seed),
+        }
+    }
+
+    /// Store the replay under `name()`, alongside the saved game
+    pub fn write(&self) -> Result<(), String> {
+        storage::write(&self.name(), &self.to_string()?)
+    }
+
+    /// Read a replay from the given file
+    pub fn read(path: &str) -> Result<Replay, String> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        Replay::from_str(&data)
+    }
+}
+
+/// Returns the faces rolled by the game between the `before` rng and `after`. Every
+/// random value the rules draw is a d6, so drawing the same number of d6 from a copy of
+/// the old rng gives exactly what was rolled.
+fn rolls_between(mut before: Rng, after: &GameState) -> Vec<u8> {
+    let count = after.rolls().saturating_sub(before.count());
+    (0..count).map(|_| before.roll_d6() as u8).collect()
+}
+
+/// Feeds a replay back through a `Board`
+pub struct ReplayPlayer {
+    replay: Replay,
+
+    /// Index of the next step to play
+    next: usize,
+
+    /// Whether steps are played automatically
+    playing: bool,
+
+    /// Seconds since the last automatic step
+    elapsed: f32,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayer { replay, next: 0, playing: false, elapsed: 0. }
+    }
+
+    /// Returns the game the replay starts from
+    pub fn start_game(&self) -> GameState {
+        self.replay.start_game()
+    }
+
+    /// Returns true once every step was played
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.steps.len()
+    }
+
+    /// Returns true while steps are played automatically
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Pause the replay if it is playing, play it otherwise
+    pub fn toggle_play(&mut self) {
+        self.playing = !self.playing;
+        self.elapsed = 0.;
+    }
+
+    /// Returns (steps played, total steps)
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next, self.replay.steps.len())
+    }
+
+    /// Play the next step on `board`. Returns an error if the dice rolled don't match
+    /// the recording, meaning the rules changed since the replay was recorded.
+    pub fn step(&mut self, board: &mut Board) -> Result<(), String> {
+        let (message, expected) = match self.replay.steps.get(self.next) {
+            Some(step) => step.clone(),
+            None => return Ok(())
+        };
+
+        self.next += 1;
+
+        let rng = board.game().rng().clone();
+        board.handle_message(BoardCommand::Game(message));
+        let rolled = rolls_between(rng, board.game());
+
+        if rolled != expected {
+            self.playing = false;
+            return Err(format!("Replay diverged at step {} ({:?}): recorded rolls {:?}, \
+                                rolled {:?}", self.next, message, expected, rolled));
+        }
+
+        Ok(())
+    }
+
+    /// Advance a playing replay by `dt` seconds, stepping when it is time to
+    pub fn update(&mut self, board: &mut Board, dt: f32) -> Result<(), String> {
+        if !self.playing {
+            return Ok(());
+        }
+
+        if self.is_finished() {
+            self.playing = false;
+            return Ok(());
+        }
+
+        self.elapsed += dt;
+        if self.elapsed < STEP_DELAY {
+            return Ok(());
+        }
+
+        self.elapsed -= STEP_DELAY;
+        self.step(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_a_resumed_game_after_its_start_turn() {
+        let mut game = GameState::new(Rng::from_seed(5));
+        assert_eq!(Replay::new(&game).name(), "5.replay");
+
+        game.current_turn = 4;
+        assert_eq!(Replay::new(&game).name(), "5.turn4.replay");
+    }
+}
@@ -1,34 +1,122 @@
-use macroquad::*;
+use serde::{Deserialize, Serialize};
 
-/// Rng seeded with rdtsc that is generated using Lehmer64
+/// Rng seeded with a u64 that is generated using Lehmer64
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Rng {
+    /// Seed used to create this Rng
+    seed: u64,
+
+    /// Number of values drawn since seeding
+    count: u64,
+
     value: u128,
+
+    /// Rolls handed out by `roll_d6` instead of random ones, used to explore every
+    /// possible outcome of an action. Never saved.
+    #[serde(skip)]
+    script: Option<Script>,
+}
+
+/// Returns the wall clock time in milliseconds
+#[cfg(not(target_arch = "wasm32"))]
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Returns the wall clock time in milliseconds. There is no system clock to ask in the
+/// browser, so ask miniquad instead.
+#[cfg(target_arch = "wasm32")]
+fn now_millis() -> u64 {
+    (macroquad::miniquad::date::now() * 1000.) as u64
+}
+
+/// A fixed list of d6 rolls
+#[derive(Debug, Clone, PartialEq)]
+struct Script {
+    rolls: Vec<usize>,
+
+    /// Index of the next roll to hand out
+    next: usize,
+
+    /// Set when more rolls were asked for than the script has
+    exhausted: bool,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new()
+    }
 }
 
 impl Rng {
+    /// Create an Rng seeded from the current wall clock time
     pub fn new() -> Rng {
-        let value = (get_frame_time() * get_time() as f32 * 0xdeadbeefcafebabe as u64 as f32);
-        let mut res = Rng {
-            value: value as u128
-        };
+        Rng::from_seed(now_millis() ^ 0xdeadbeefcafebabe)
+    }
+
+    /// Create an Rng that always produces the same values for the same `seed`
+    pub fn from_seed(seed: u64) -> Rng {
+        // Lehmer64 requires an odd state, so force the low bit on
+        let value = ((seed as u128) << 64) | (!seed as u128) | 1;
+        let mut res = Rng { seed, count: 0, value, script: None };
 
         // Cycle through to create some chaos
         for _ in 0..100 {
-            let _ = res.next();
+            let _ = res.next_u64();
         }
 
+        res.count = 0;
         res
     }
 
-    pub fn next(&mut self) -> u64 {
+    /// Returns the seed this Rng was created with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the number of values drawn since seeding
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the next raw 64-bit value
+    pub fn next_u64(&mut self) -> u64 {
+        self.count += 1;
         self.value = self.value.wrapping_mul(0xda942042e4dd58b5);
         (self.value >> 64) as u64
     }
 
     /// Returns [1, 6]
     pub fn roll_d6(&mut self) -> usize {
-        (self.next() % 6 + 1) as usize
+        if let Some(script) = &mut self.script {
+            self.count += 1;
+
+            // Past the end of the script, keep going with 1s so the caller can finish
+            // and notice with `script_exhausted`
+            return match script.rolls.get(script.next) {
+                Some(&roll) => {
+                    script.next += 1;
+                    roll
+                }
+                None => {
+                    script.exhausted = true;
+                    1
+                }
+            };
+        }
+
+        (self.next_u64() % 6 + 1) as usize
     }
-}
 
+    /// Make `roll_d6` return the given rolls in order instead of random ones
+    pub fn script(&mut self, rolls: Vec<usize>) {
+        self.script = Some(Script { rolls, next: 0, exhausted: false });
+    }
 
+    /// Returns true if `roll_d6` was called more often than the script has rolls
+    pub fn script_exhausted(&self) -> bool {
+        self.script.as_ref().is_some_and(|script| script.exhausted)
+    }
+}
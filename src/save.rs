@@ -0,0 +1,94 @@
+//! Saving the game in progress so it survives closing the window. Native builds write
+//! a file next to the executable, the wasm build uses the browser's localStorage.
+use crate::game::GameState;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+
+/// Version of the save format. Bump this whenever `GameState` changes shape so old
+/// saves are ignored instead of loaded wrong.
+pub const SAVE_VERSION: u32 = 1;
+
+/// Name the game is stored under
+const SAVE_NAME: &str = "sav";
+
+/// What is actually written to storage
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    game: GameState,
+}
+
+/// Serialize the full state of `game`, including its Rng, into the save format
+pub fn to_string(game: &GameState) -> Result<String, String> {
+    let save = SaveFile { version: SAVE_VERSION, game: game.clone() };
+    ron::ser::to_string(&save).map_err(|e| format!("Failed to serialize save: {}", e))
+}
+
+/// Parse a game written by `to_string`
+pub fn from_str(data: &str) -> Result<GameState, String> {
+    #[derive(Deserialize)]
+    struct Version {
+        version: u32,
+    }
+
+    // Check the version on its own first so a save from another version gives a clear
+    // error instead of a confusing parse failure
+    let version = ron::de::from_str::<Version>(data)
+        .map_err(|e| format!("Failed to parse save: {}", e))?
+        .version;
+
+    if version != SAVE_VERSION {
+        return Err(format!("Save is version {}, expected {}", version, SAVE_VERSION));
+    }
+
+    let save: SaveFile = ron::de::from_str(data)
+        .map_err(|e| format!("Failed to parse save: {}", e))?;
+
+    Ok(save.game)
+}
+
+/// Save `game` so it can be resumed with `load`
+pub fn store(game: &GameState) -> Result<(), String> {
+    storage::write(SAVE_NAME, &to_string(game)?)
+}
+
+/// Load the saved game, if there is one
+pub fn load() -> Result<Option<GameState>, String> {
+    match storage::read(SAVE_NAME)? {
+        Some(data) => from_str(&data).map(Some),
+        None => Ok(None)
+    }
+}
+
+/// Forget the saved game, once it is finished
+pub fn clear() -> Result<(), String> {
+    storage::remove(SAVE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::BoardMessage;
+    use crate::rng::Rng;
+
+    #[test]
+    fn round_trips_a_game() {
+        let mut game = GameState::new(Rng::from_seed(7));
+        game.handle_message(BoardMessage::ChooseDie2);
+        game.handle_message(BoardMessage::ChooseNextTile);
+
+        let loaded = from_str(&to_string(&game).unwrap()).unwrap();
+        assert!(loaded == game);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let game = GameState::new(Rng::from_seed(7));
+        let data = to_string(&game).unwrap()
+            .replacen(&format!("version:{}", SAVE_VERSION), "version:0", 1);
+
+        assert_eq!(from_str(&data).err(),
+                   Some(format!("Save is version 0, expected {}", SAVE_VERSION)));
+        assert!(from_str("(game: ())").err().unwrap().starts_with("Failed to parse save"));
+    }
+}
@@ -0,0 +1,168 @@
+//! Plays many games without a window to measure how a set of rules and encounter tables
+//! play out
+use crate::game::*;
+use crate::policy::Policy;
+use crate::rng::Rng;
+use crate::tables::Difficulty;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Decisions made in a single game before giving up on it. A policy that needs more
+/// than this is stuck repeating a decision that does nothing.
+const MAX_DECISIONS: usize = 10_000;
+
+/// Hazards in the order they are reported
+const HAZARDS: [Hazard; 4] =
+    [Hazard::SneakBeast, Hazard::BeastAttack, Hazard::Trap, Hazard::BrokenWall];
+
+/// Items in the order they are reported
+const ITEMS: [Item; 7] = [Item::Charm, Item::Machete, Item::Pickaxe, Item::Shotgun,
+                          Item::Bandage, Item::Elixir, Item::Idol];
+
+/// Totals over every simulated game
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Number of games played
+    pub games: usize,
+
+    /// Games won by escaping with the idol
+    pub wins: usize,
+
+    /// Games lost by running out of health
+    pub deaths: usize,
+
+    /// Games lost by running out of turns
+    pub out_of_time: usize,
+
+    /// Games the policy never finished
+    pub stuck: usize,
+
+    /// Sum of the turn reached in every game
+    pub turns: usize,
+
+    /// For each hazard, how many times it dealt each amount of damage
+    pub damage: HashMap<Hazard, HashMap<usize, usize>>,
+
+    /// For each item, the number of games it was found in
+    pub items: HashMap<Item, usize>,
+}
+
+impl Report {
+    /// Returns the fraction of games that were won
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+
+    /// Returns the average turn the games ended on
+    pub fn average_turns(&self) -> f64 {
+        self.turns as f64 / self.games.max(1) as f64
+    }
+
+    /// Add a single finished game to the totals
+    fn add_game(&mut self, game: &GameState, outcomes: &[Outcome]) {
+        self.games += 1;
+        self.turns += game.current_turn;
+
+        match game.state {
+            BoardState::Victory => self.wins += 1,
+            BoardState::GameOver if outcomes.contains(&Outcome::OutOfTime) => {
+                self.out_of_time += 1;
+            }
+            BoardState::GameOver => self.deaths += 1,
+            _ => self.stuck += 1,
+        }
+
+        let mut found = Vec::new();
+        for outcome in outcomes {
+            match *outcome {
+                Outcome::Damaged { hazard, amount, .. } if amount > 0 => {
+                    *self.damage.entry(hazard).or_default().entry(amount).or_default() += 1;
+                }
+                Outcome::ItemFound(item) if !found.contains(&item) => found.push(item),
+                _ => {}
+            }
+        }
+
+        for item in found {
+            *self.items.entry(item).or_default() += 1;
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let games = self.games.max(1) as f64;
+
+        writeln!(f, "Games:          {}", self.games)?;
+        writeln!(f, "Win rate:       {:.2}%", self.win_rate() * 100.)?;
+        writeln!(f, "Deaths:         {:.2}%", self.deaths as f64 / games * 100.)?;
+        writeln!(f, "Out of time:    {:.2}%", self.out_of_time as f64 / games * 100.)?;
+        if self.stuck > 0 {
+            writeln!(f, "Stuck:          {}", self.stuck)?;
+        }
+        writeln!(f, "Average turns:  {:.2}", self.average_turns())?;
+
+        writeln!(f, "\nDamage by encounter (amount: hits)")?;
+        for hazard in HAZARDS.iter() {
+            let hits = match self.damage.get(hazard) {
+                Some(hits) => hits,
+                None => {
+                    writeln!(f, "  {:?}: none", hazard)?;
+                    continue;
+                }
+            };
+
+            let mut amounts = hits.iter().collect::<Vec<_>>();
+            amounts.sort();
+
+            let count = amounts.iter().map(|(_, &n)| n).sum::<usize>();
+            let total = amounts.iter().map(|(&amount, &n)| amount * n).sum::<usize>();
+            let amounts = amounts.iter()
+                .map(|(amount, n)| format!("{}: {}", amount, n))
+                .collect::<Vec<_>>();
+
+            writeln!(f, "  {:?}: {:.2} per game, {:.2} average damage [{}]", hazard,
+                     count as f64 / games, total as f64 / count as f64, amounts.join(", "))?;
+        }
+
+        writeln!(f, "\nItems found (% of games)")?;
+        for item in ITEMS.iter() {
+            let found = self.items.get(item).copied().unwrap_or(0);
+            writeln!(f, "  {:?}: {:.2}%", item, found as f64 / games * 100.)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Play `game` to the end with `policy`, returning everything that happened
+pub fn play(game: &mut GameState, policy: &mut dyn Policy) -> Vec<Outcome> {
+    let mut outcomes = Vec::new();
+
+    for _ in 0..MAX_DECISIONS {
+        if game.is_over() {
+            break;
+        }
+
+        for message in policy.plan(game) {
+            outcomes.extend(game.handle_message(message));
+        }
+    }
+
+    outcomes
+}
+
+/// Play `games` games in the bundled temple at `difficulty` with `policy`, seeded from
+/// `first_seed` onwards so a run can be repeated exactly
+pub fn simulate(games: usize, first_seed: u64, difficulty: &Difficulty,
+                policy: &mut dyn Policy) -> Report {
+    let mut report = Report::default();
+
+    for seed in first_seed..first_seed + games as u64 {
+        let mut game = GameState::with_difficulty(Rng::from_seed(seed), difficulty.clone());
+        let outcomes = play(&mut game, policy);
+        report.add_game(&game, &outcomes);
+    }
+
+    report
+}
@@ -0,0 +1,217 @@
+//! Expectimax search over the rules engine, used to find the dice assignment and wall
+//! orientation that lose the least health on average.
+//!
+//! Instead of modelling the rules separately, the search plays every choice on a copy
+//! of the game and feeds it every possible roll through `GameState::script_rolls`, so
+//! it always agrees with the engine.
+use crate::game::*;
+
+/// Rolls looked ahead within a single action. Only a chain of 6s on the Next Tile
+/// reroll can need more, which is rare enough that the search stops there and counts
+/// the health from before the action instead of playing on with made up rolls.
+const MAX_ROLLS: usize = 4;
+
+/// A single choice during `AssignDice` and how much health it is expected to cost
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hint {
+    /// Die (1 or 2) placed on the Next Tile. The other one goes on the Encounter.
+    pub next_tile_die: usize,
+
+    /// Orientation to draw the walls in, or None if the Next Tile is rerolled first so
+    /// the orientation can't be picked yet
+    pub orientation: Option<usize>,
+
+    /// Health expected to be lost by the end of the search
+    pub expected_loss: f64,
+}
+
+/// Returns every dice assignment and wall orientation for the current `AssignDice`
+/// state, with the health expected to be lost over the next `depth` turns when playing
+/// the best choices afterwards. Returns nothing outside of `AssignDice` or once a die
+/// has been placed.
+pub fn hints(game: &GameState, depth: usize) -> Vec<Hint> {
+    let mut hints = Vec::new();
+
+    let (die1, die2) = match (game.state, game.die1, game.die2) {
+        (BoardState::AssignDice, Some(die1), Some(die2)) => (die1, die2),
+        _ => return hints
+    };
+
+    let health = game.health as f64;
+    for &(next_tile_die, next_tile) in [(1, die1), (2, die2)].iter() {
+        let mut assigned = game.clone();
+        for &message in assignment(next_tile_die).iter() {
+            assigned.handle_message(message);
+        }
+
+        if next_tile == 6 {
+            let value = expect(&assigned, &[BoardMessage::NextState], depth);
+            hints.push(Hint {
+                next_tile_die,
+                orientation: None,
+                expected_loss: health - value
+            });
+            continue;
+        }
+
+        // No rolls are needed to go on to drawing the walls
+        assigned.handle_message(BoardMessage::NextState);
+
+        for orientation in orientations(next_tile) {
+            let value = expect(&assigned, &rotate(&assigned, orientation), depth);
+            hints.push(Hint {
+                next_tile_die,
+                orientation: Some(orientation),
+                expected_loss: health - value
+            });
+        }
+    }
+
+    hints
+}
+
+/// Returns the messages placing `next_tile_die` on the Next Tile and the other die on
+/// the Encounter
+fn assignment(next_tile_die: usize) -> [BoardMessage; 4] {
+    if next_tile_die == 1 {
+        [BoardMessage::ChooseDie1, BoardMessage::ChooseNextTile,
+         BoardMessage::ChooseDie2, BoardMessage::ChooseEncounter]
+    } else {
+        [BoardMessage::ChooseDie2, BoardMessage::ChooseNextTile,
+         BoardMessage::ChooseDie1, BoardMessage::ChooseEncounter]
+    }
+}
+
+/// Returns the orientations of the Next Tile that draw different walls
+fn orientations(next_tile: usize) -> Vec<usize> {
+    let mut orientations: Vec<usize> = Vec::new();
+    for orientation in 0..4 {
+        let walls = walls_for_tile(next_tile, orientation);
+        if !orientations.iter().any(|&seen| walls_for_tile(next_tile, seen) == walls) {
+            orientations.push(orientation);
+        }
+    }
+
+    orientations
+}
+
+/// Returns the messages turning the walls to `orientation` and drawing them
+fn rotate(game: &GameState, orientation: usize) -> Vec<BoardMessage> {
+    let turns = (orientation + 4 - game.wall_orientation % 4) % 4;
+    let mut messages = vec![BoardMessage::ChangeWallOrientation; turns];
+    messages.push(BoardMessage::NextState);
+    messages
+}
+
+/// Returns the expected health after sending `messages` to `game`, averaged over every
+/// roll they need
+fn expect(game: &GameState, messages: &[BoardMessage], depth: usize) -> f64 {
+    expect_rolls(game, messages, &mut Vec::new(), depth)
+}
+
+fn expect_rolls(game: &GameState, messages: &[BoardMessage], rolls: &mut Vec<usize>,
+                depth: usize) -> f64 {
+    let mut next = game.clone();
+    next.script_rolls(rolls.clone());
+    for &message in messages {
+        next.handle_message(message);
+    }
+
+    // The messages needed more rolls than are looked ahead, so stop searching here
+    if next.script_exhausted() && rolls.len() == MAX_ROLLS {
+        return game.health as f64;
+    }
+
+    // The messages needed another roll, so try each face of it
+    if next.script_exhausted() {
+        let mut total = 0.;
+        for roll in 1..=6 {
+            rolls.push(roll);
+            total += expect_rolls(game, messages, rolls, depth);
+            rolls.pop();
+        }
+
+        return total / 6.;
+    }
+
+    value(&next, depth)
+}
+
+/// Returns the expected health after `depth` more turns, making the best choice at
+/// every decision
+fn value(game: &GameState, depth: usize) -> f64 {
+    if game.is_over() {
+        return game.health as f64;
+    }
+
+    let best = |choices: Vec<Vec<BoardMessage>>| {
+        let mut best = f64::NEG_INFINITY;
+        for messages in &choices {
+            best = best.max(expect(game, messages, depth));
+
+            // Nothing ends with more than full health, so no other choice can do better
+            if best >= MAX_HEALTH as f64 {
+                break;
+            }
+        }
+
+        best
+    };
+
+    match game.state {
+        BoardState::AssignDice => {
+            match (game.die1, game.die2) {
+                (Some(_), Some(_)) => {
+                    best((1..=2).map(|die| {
+                        let mut messages = assignment(die).to_vec();
+                        messages.push(BoardMessage::NextState);
+                        messages
+                    }).collect())
+                }
+                _ => expect(game, &[BoardMessage::NextState], depth)
+            }
+        }
+        BoardState::DrawWalls | BoardState::ShortcutDrawWalls => {
+            match game.next_tile {
+                Some(next_tile) => best(orientations(next_tile).into_iter()
+                    .map(|orientation| rotate(game, orientation))
+                    .collect()),
+                None => expect(game, &[BoardMessage::NextState], depth)
+            }
+        }
+        BoardState::Movement | BoardState::ShortcutMovement => {
+            let mut choices = Vec::new();
+            for neighbor in game.get_neighbors() {
+                let step = BoardMessage::MoveToLocation(neighbor);
+                choices.push(vec![step, BoardMessage::NextState]);
+
+                if game.pickaxe > 0 && game.is_wall_built(neighbor.0) {
+                    choices.push(vec![step, BoardMessage::TogglePickaxe,
+                                      BoardMessage::NextState]);
+                }
+            }
+
+            best(choices)
+        }
+        BoardState::ChooseTeleport | BoardState::ShortcutChooseTeleport => {
+            let mut choices = vec![vec![BoardMessage::NextState]];
+            for &teleport in &game.map.teleports {
+                choices.push(vec![BoardMessage::ChooseTeleport(teleport),
+                                  BoardMessage::NextState]);
+            }
+
+            best(choices)
+        }
+        BoardState::DefendAttack => {
+            let mut choices = vec![vec![BoardMessage::NextState]];
+            if game.shotgun > 0 {
+                choices.push(vec![BoardMessage::UseShotgun]);
+            }
+
+            best(choices)
+        }
+        BoardState::EndTurn if depth <= 1 => game.health as f64,
+        BoardState::EndTurn => expect(game, &[BoardMessage::NextState], depth - 1),
+        _ => expect(game, &[BoardMessage::NextState], depth),
+    }
+}
@@ -0,0 +1,89 @@
+//! Small named pieces of data kept between runs, such as the saved game. Native builds
+//! write a `templeantics.<name>` file next to the executable, the wasm build uses the
+//! browser's localStorage under `templeantics_<name>`.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    /// Returns the file `name` is stored in
+    fn path(name: &str) -> String {
+        format!("templeantics.{}", name)
+    }
+
+    pub fn write(name: &str, data: &str) -> Result<(), String> {
+        std::fs::write(path(name), data)
+            .map_err(|e| format!("Failed to write {}: {}", path(name), e))
+    }
+
+    pub fn read(name: &str) -> Result<Option<String>, String> {
+        match std::fs::read_to_string(path(name)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", path(name), e))
+        }
+    }
+
+    pub fn remove(name: &str) -> Result<(), String> {
+        match std::fs::remove_file(path(name)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to remove {}: {}", path(name), e))
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    // Implemented by the storage plugin in index.html
+    extern "C" {
+        fn storage_write(name: *const u8, name_len: u32, ptr: *const u8, len: u32);
+        fn storage_len(name: *const u8, name_len: u32) -> i32;
+        fn storage_read(name: *const u8, name_len: u32, ptr: *mut u8, len: u32);
+        fn storage_remove(name: *const u8, name_len: u32);
+    }
+
+    pub fn write(name: &str, data: &str) -> Result<(), String> {
+        unsafe {
+            storage_write(name.as_ptr(), name.len() as u32, data.as_ptr(),
+                          data.len() as u32)
+        };
+        Ok(())
+    }
+
+    pub fn read(name: &str) -> Result<Option<String>, String> {
+        // A negative length means there is nothing stored
+        let len = unsafe { storage_len(name.as_ptr(), name.len() as u32) };
+        if len < 0 {
+            return Ok(None);
+        }
+
+        let mut buf = vec![0u8; len as usize];
+        unsafe {
+            storage_read(name.as_ptr(), name.len() as u32, buf.as_mut_ptr(),
+                         buf.len() as u32)
+        };
+
+        String::from_utf8(buf)
+            .map(Some)
+            .map_err(|e| format!("Stored {} is not valid UTF-8: {}", name, e))
+    }
+
+    pub fn remove(name: &str) -> Result<(), String> {
+        unsafe { storage_remove(name.as_ptr(), name.len() as u32) };
+        Ok(())
+    }
+}
+
+/// Store `data` under `name`, replacing what was there
+pub fn write(name: &str, data: &str) -> Result<(), String> {
+    backend::write(name, data)
+}
+
+/// Returns the data stored under `name`, if any
+pub fn read(name: &str) -> Result<Option<String>, String> {
+    backend::read(name)
+}
+
+/// Forget the data stored under `name`
+pub fn remove(name: &str) -> Result<(), String> {
+    backend::remove(name)
+}
@@ -0,0 +1,238 @@
+//! Encounter and damage tables, loaded from `static/encounters.ron` so difficulty can be
+//! rebalanced without touching the rules engine.
+use serde::{Deserialize, Serialize};
+
+/// One row of an attack table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttackRow {
+    /// Lowest roll of the attack die covered by this row
+    pub min_roll: usize,
+
+    /// Highest roll of the attack die covered by this row
+    pub max_roll: usize,
+
+    /// Damage dealt in each turn band
+    pub damage: Vec<usize>,
+}
+
+/// All of the encounter numbers for a single difficulty
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Difficulty {
+    /// Name shown to the player
+    pub name: String,
+
+    /// Last turn of each turn band, in increasing order
+    pub turn_bands: Vec<usize>,
+
+    /// Health restored by resting at a campfire
+    pub campfire_heal: usize,
+
+    /// Damage table for a sneak beast attack
+    pub sneak_beast: Vec<AttackRow>,
+
+    /// Damage table for a beast attack
+    pub beast_attack: Vec<AttackRow>,
+
+    /// Damage from falling into a trap in each turn band
+    pub trap: Vec<usize>,
+}
+
+impl Difficulty {
+    /// Returns the index of the turn band containing `turn`. Turns past the final band
+    /// stay in the final band.
+    pub fn band(&self, turn: usize) -> usize {
+        self.turn_bands.iter()
+            .position(|&last_turn| turn <= last_turn)
+            .unwrap_or(self.turn_bands.len() - 1)
+    }
+
+    /// Returns the damage dealt by `table` for the given attack roll on `turn`
+    pub fn attack_damage(&self, table: &[AttackRow], roll: usize, turn: usize) -> usize {
+        let row = table.iter()
+            .find(|row| row.min_roll <= roll && roll <= row.max_roll)
+            .unwrap_or_else(|| panic!("{}: No attack row for roll {}", self.name, roll));
+
+        row.damage[self.band(turn)]
+    }
+
+    /// Returns the lowest and highest damage `table` can deal on `turn`
+    pub fn attack_range(&self, table: &[AttackRow], turn: usize) -> (usize, usize) {
+        let band = self.band(turn);
+        let damages = table.iter().map(|row| row.damage[band]);
+        (damages.clone().min().unwrap_or(0), damages.max().unwrap_or(0))
+    }
+
+    /// Returns the damage dealt by a trap on `turn`
+    pub fn trap_damage(&self, turn: usize) -> usize {
+        self.trap[self.band(turn)]
+    }
+
+    /// Check that every table covers every roll of a d6 and every turn band
+    fn validate(&self) -> Result<(), String> {
+        let bands = self.turn_bands.len();
+        if bands == 0 {
+            return Err(format!("{}: No turn bands", self.name));
+        }
+
+        if self.turn_bands.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(format!("{}: turn_bands must strictly increase", self.name));
+        }
+
+        if self.trap.len() != bands {
+            return Err(format!("{}: trap needs {} damages", self.name, bands));
+        }
+
+        for (table_name, table) in [("sneak_beast", &self.sneak_beast),
+                                    ("beast_attack", &self.beast_attack)].iter() {
+            for roll in 1..=6 {
+                let rows = table.iter()
+                    .filter(|row| row.min_roll <= roll && roll <= row.max_roll)
+                    .count();
+                if rows != 1 {
+                    return Err(format!("{}: {} covers roll {} {} times",
+                                       self.name, table_name, roll, rows));
+                }
+            }
+
+            if let Some(row) = table.iter().find(|row| row.damage.len() != bands) {
+                return Err(format!("{}: {} row {}..={} needs {} damages",
+                                   self.name, table_name, row.min_roll, row.max_roll,
+                                   bands));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse and validate a list of difficulties from RON
+pub fn parse_difficulties(input: &str) -> Result<Vec<Difficulty>, String> {
+    let difficulties: Vec<Difficulty> = ron::de::from_str(input)
+        .map_err(|err| format!("Invalid encounter tables: {}", err))?;
+
+    if difficulties.is_empty() {
+        return Err("No difficulties in encounter tables".to_string());
+    }
+
+    for difficulty in &difficulties {
+        difficulty.validate()?;
+    }
+
+    Ok(difficulties)
+}
+
+/// Returns the difficulties bundled with the game
+pub fn bundled_difficulties() -> Vec<Difficulty> {
+    parse_difficulties(include_str!("../static/encounters.ron"))
+        .expect("Bundled encounter tables are invalid")
+}
+
+/// Returns the difficulty called `name`, ignoring case
+pub fn find_difficulty(difficulties: &[Difficulty], name: &str) -> Result<Difficulty, String> {
+    difficulties.iter()
+        .find(|difficulty| difficulty.name.eq_ignore_ascii_case(name))
+        .cloned()
+        .ok_or_else(|| {
+            let names = difficulties.iter()
+                .map(|difficulty| difficulty.name.as_str())
+                .collect::<Vec<_>>();
+            format!("Unknown difficulty {}, expected one of: {}", name, names.join(", "))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A single valid difficulty with two turn bands
+    const TABLES: &str = r#"[(
+        name: "Test",
+        turn_bands: [3, 6],
+        campfire_heal: 1,
+        sneak_beast: [
+            (min_roll: 1, max_roll: 3, damage: [1, 2]),
+            (min_roll: 4, max_roll: 6, damage: [3, 4]),
+        ],
+        beast_attack: [
+            (min_roll: 1, max_roll: 6, damage: [1, 1]),
+        ],
+        trap: [1, 2],
+    )]"#;
+
+    /// Returns the valid test difficulty
+    fn difficulty() -> Difficulty {
+        parse_difficulties(TABLES).unwrap().remove(0)
+    }
+
+    #[test]
+    fn looks_up_damage_by_roll_and_turn() {
+        let difficulty = difficulty();
+        assert_eq!(difficulty.attack_damage(&difficulty.sneak_beast, 2, 1), 1);
+        assert_eq!(difficulty.attack_damage(&difficulty.sneak_beast, 5, 4), 4);
+        assert_eq!(difficulty.attack_range(&difficulty.sneak_beast, 10), (2, 4));
+        assert_eq!(difficulty.trap_damage(3), 1);
+    }
+
+    #[test]
+    fn rejects_no_turn_bands() {
+        let mut difficulty = difficulty();
+        difficulty.turn_bands.clear();
+        assert_eq!(difficulty.validate().unwrap_err(), "Test: No turn bands");
+    }
+
+    #[test]
+    fn rejects_turn_bands_out_of_order() {
+        let mut difficulty = difficulty();
+        difficulty.turn_bands = vec![6, 3];
+        assert_eq!(difficulty.validate().unwrap_err(),
+                   "Test: turn_bands must strictly increase");
+
+        difficulty.turn_bands = vec![3, 3];
+        assert_eq!(difficulty.validate().unwrap_err(),
+                   "Test: turn_bands must strictly increase");
+    }
+
+    #[test]
+    fn rejects_trap_missing_a_band() {
+        let mut difficulty = difficulty();
+        difficulty.trap.pop();
+        assert_eq!(difficulty.validate().unwrap_err(), "Test: trap needs 2 damages");
+    }
+
+    #[test]
+    fn rejects_a_roll_no_row_covers() {
+        let mut difficulty = difficulty();
+        difficulty.sneak_beast[1].min_roll = 5;
+        assert_eq!(difficulty.validate().unwrap_err(),
+                   "Test: sneak_beast covers roll 4 0 times");
+    }
+
+    #[test]
+    fn rejects_a_roll_two_rows_cover() {
+        let mut difficulty = difficulty();
+        difficulty.sneak_beast[0].max_roll = 4;
+        assert_eq!(difficulty.validate().unwrap_err(),
+                   "Test: sneak_beast covers roll 4 2 times");
+    }
+
+    #[test]
+    fn rejects_a_row_missing_a_band() {
+        let mut difficulty = difficulty();
+        difficulty.beast_attack[0].damage.pop();
+        assert_eq!(difficulty.validate().unwrap_err(),
+                   "Test: beast_attack row 1..=6 needs 2 damages");
+    }
+
+    #[test]
+    fn rejects_no_difficulties() {
+        assert_eq!(parse_difficulties("[]").unwrap_err(),
+                   "No difficulties in encounter tables");
+    }
+
+    #[test]
+    fn rejects_malformed_tables() {
+        let error = parse_difficulties(&TABLES.replace("campfire_heal: 1", "campfire_heal: -1"))
+            .unwrap_err();
+        assert!(error.starts_with("Invalid encounter tables"));
+    }
+}
@@ -0,0 +1,87 @@
+//! Text drawn with the bundled Telegrama font. The font is loaded once at startup and
+//! kept here so every `Button` can use it without being handed it.
+use crate::*;
+use std::cell::Cell;
+
+/// Path of the bundled font
+const FONT_PATH: &str = "./static/telegrama_raw.otf";
+
+thread_local! {
+    /// The bundled font, once loaded
+    static FONT: Cell<Option<Font>> = const { Cell::new(None) };
+}
+
+/// Size of a line of text in pixels
+#[derive(Debug, Copy, Clone)]
+pub struct Extent {
+    pub width: f32,
+    pub height: f32,
+
+    /// Distance from the top of the text down to its baseline
+    pub ascent: f32,
+}
+
+/// Load the bundled font. Text is drawn with the default font until it is loaded, or
+/// if it fails to load.
+pub async fn load_font() {
+    match load_ttf_font(FONT_PATH).await {
+        Ok(font) => FONT.with(|cell| cell.set(Some(font))),
+        Err(e) => info!("Failed to load {}: {:?}", FONT_PATH, e),
+    }
+}
+
+/// Returns the parameters drawing text with the bundled font
+fn params(size: f32, color: Color) -> TextParams {
+    let font_size = size as u16;
+    match FONT.with(|cell| cell.get()) {
+        Some(font) => TextParams { font, font_size, color, ..Default::default() },
+        None => TextParams { font_size, color, ..Default::default() },
+    }
+}
+
+/// Returns the size of `text` drawn at the given font size
+pub fn measure(text: &str, size: f32) -> Extent {
+    let dims = measure_text(text, FONT.with(|cell| cell.get()), size as u16, 1.);
+    Extent { width: dims.width, height: dims.height, ascent: dims.offset_y }
+}
+
+/// Draw `text` with its baseline at `y`
+pub fn draw(text: &str, x: f32, y: f32, size: f32, color: Color) {
+    draw_text_ex(text, x, y, params(size, color));
+}
+
+/// Draw `text` with its top at `y`
+pub fn draw_top(text: &str, x: f32, y: f32, size: f32, color: Color) {
+    draw(text, x, y + measure(text, size).ascent, size, color);
+}
+
+/// Returns the largest font size, up to 100, at which `text` fits in 90% of a `w` by
+/// `h` box
+pub fn fit(text: &str, w: f32, h: f32) -> f32 {
+    let fits = |size: u16| {
+        let extent = measure(text, size as f32);
+        extent.width < w * 0.9 && extent.height < h * 0.9
+    };
+
+    // Text grows with its size, so search for the last size that fits
+    let (mut low, mut high) = (0u16, 100u16);
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    low as f32
+}
+
+/// Draw `text` centered in the given box, both horizontally and on its baseline
+pub fn draw_centered(text: &str, (x, y, w, h): (f32, f32, f32, f32), size: f32,
+                     color: Color) {
+    let extent = measure(text, size);
+    let text_x = x + (w - extent.width) / 2.;
+    let baseline = y + (h - extent.height) / 2. + extent.ascent;
+    draw(text, text_x, baseline, size, color);
+}
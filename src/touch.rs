@@ -0,0 +1,118 @@
+//! Touch input for phones. Short taps act like mouse clicks and holding a finger still
+//! asks for a tooltip.
+use crate::*;
+
+/// Seconds a finger has to be held still to count as a long press
+const LONG_PRESS: f64 = 0.5;
+
+/// Pixels a finger can drift and still count as held still
+const MAX_DRIFT: f32 = 10.;
+
+/// Something done with a finger
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Gesture {
+    /// A short tap where the finger went down at `press` and lifted at `release`,
+    /// handled like a click
+    Tap { press: (f32, f32), release: (f32, f32) },
+
+    /// A finger held still at the given position
+    LongPress((f32, f32)),
+}
+
+/// The finger currently on the screen
+struct Press {
+    id: u64,
+
+    /// Where and when the finger went down
+    start: (f32, f32),
+    time: f64,
+
+    /// Set once the long press was reported, so releasing it isn't also a tap
+    long: bool,
+
+    /// Set once the finger drifted too far to be a tap or long press
+    moved: bool,
+}
+
+/// Turns the raw touches of each frame into gestures
+pub struct TouchTracker {
+    press: Option<Press>,
+
+    /// Fingers that were on the screen last frame
+    down: Vec<u64>,
+}
+
+impl TouchTracker {
+    pub fn new() -> Self {
+        TouchTracker { press: None, down: Vec::new() }
+    }
+
+    /// Returns true if a finger is on the screen. Browsers also send mouse events for
+    /// touches, which should be ignored while this is set.
+    pub fn is_touching(&self) -> bool {
+        self.press.is_some()
+    }
+
+    /// Process this frame's touches, returning the gestures that finished
+    pub fn update(&mut self) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+        let now = get_time();
+
+        let touches = touches();
+        for touch in &touches {
+            let pos = (touch.position.x, touch.position.y);
+            match touch.phase {
+                TouchPhase::Started => {
+                    // Only follow the first finger
+                    if self.press.is_none() {
+                        self.press = Some(Press { id: touch.id, start: pos, time: now,
+                                                  long: false, moved: false });
+                    }
+                }
+                TouchPhase::Moved | TouchPhase::Stationary => {
+                    if let Some(press) = self.press.as_mut().filter(|p| p.id == touch.id) {
+                        let (dx, dy) = (pos.0 - press.start.0, pos.1 - press.start.1);
+                        if (dx * dx + dy * dy).sqrt() > MAX_DRIFT {
+                            press.moved = true;
+                        }
+                    }
+                }
+                TouchPhase::Ended => match self.press.take() {
+                    Some(press) if press.id != touch.id => self.press = Some(press),
+                    Some(press) if !press.long && !press.moved => {
+                        gestures.push(Gesture::Tap { press: press.start, release: pos });
+                    }
+                    Some(_) => {}
+
+                    // Touches only keep their latest phase, so a finger that went down
+                    // and lifted within this frame is only ever seen lifting
+                    None if !self.down.contains(&touch.id) => {
+                        gestures.push(Gesture::Tap { press: pos, release: pos });
+                    }
+                    None => {}
+                },
+                TouchPhase::Cancelled => {
+                    if self.press.as_ref().is_some_and(|p| p.id == touch.id) {
+                        self.press = None;
+                    }
+                }
+            }
+        }
+
+        self.down = touches.iter()
+            .filter(|touch| !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled))
+            .map(|touch| touch.id)
+            .collect();
+
+        // Report a long press as soon as it has been held long enough, while the finger
+        // is still down
+        if let Some(press) = &mut self.press {
+            if !press.long && !press.moved && now - press.time >= LONG_PRESS {
+                press.long = true;
+                gestures.push(Gesture::LongPress(press.start));
+            }
+        }
+
+        gestures
+    }
+}
@@ -9,16 +9,7 @@ pub trait Drawable {
 
     fn draw(&self) {
         // Draw the current image
-        draw_texture_ex(
-            self.texture(),
-            0.,
-            0.,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(vec2(screen_width(), screen_height())),
-                ..Default::default()
-            }
-        );
+        layout::draw_board_texture(self.texture());
     }
 
     fn debug_draw(&self) {
@@ -34,7 +25,7 @@ pub trait Drawable {
                 draw_rectangle_lines(x, y, w, h, 2., RED);
 
                 // Draw text
-                draw_text(&format!("{}", i), x + 2., y + 2., 20., GREEN);
+                text::draw_top(&format!("{}", i), x + 2., y + 2., 20., GREEN);
             }
         }
     }
@@ -46,11 +37,25 @@ pub trait Clickable {
     type Message;
 
     /// Return a Message corresponding to that click
-    fn handle_click(&mut self, pos: (f32, f32)) -> Option<Self::Message> {
+    fn handle_click(&mut self, _pos: (f32, f32)) -> Option<Self::Message> {
         None
     }
 
     /// Update the state of Self, based on a given message
-    fn handle_message(&mut self, message: Self::Message) { }
+    fn handle_message(&mut self, _message: Self::Message) { }
+
+    /// Return a Message for a click pressed at `press` and released at `release`. The
+    /// click only counts if both land on the same target.
+    fn handle_press_release(&mut self, press: (f32, f32), release: (f32, f32))
+            -> Option<Self::Message> where Self::Message: PartialEq {
+        let pressed = self.handle_click(press)?;
+        let released = self.handle_click(release)?;
+        if pressed == released { Some(released) } else { None }
+    }
+
+    /// Return a short explanation of whatever is at the given position
+    fn tooltip(&self, _pos: (f32, f32)) -> Option<String> {
+        None
+    }
 }
 
@@ -0,0 +1,188 @@
+//! Draws the board from the map and the clickables instead of the baked `board.png`, so
+//! temples of any size render crisply at any resolution. Only the printed parts of the
+//! board are drawn here. The board draws the game on top exactly as it does over the
+//! image.
+use crate::*;
+use board::{ButtonId, TILE_OFFSET};
+use templeantics::map::Map;
+use templeantics::tables::Difficulty;
+
+/// Area of the board the turn track fills, matching the track printed on board.png
+const TURN_TRACK: (f32, f32, f32, f32) = (0.2977, 0.119, 0.4595, 0.0227);
+
+/// Colors of the vector drawn board
+#[derive(Debug, Copy, Clone)]
+pub struct Theme {
+    /// Name of the theme
+    pub name: &'static str,
+
+    /// Behind everything
+    pub background: Color,
+
+    /// Behind the dice, item and turn slots
+    pub slot: Color,
+
+    /// Floor of a tile
+    pub tile: Color,
+
+    /// Grid and slot outlines
+    pub line: Color,
+
+    /// Labels
+    pub text: Color,
+
+    /// Marks the exits
+    pub exit: Color,
+
+    /// Marks the caves that can be teleported between
+    pub teleport: Color,
+
+    /// Names the items handed out by tiles
+    pub item: Color,
+}
+
+impl Theme {
+    /// Sandstone, close to the printed board
+    pub fn temple() -> Theme {
+        Theme {
+            name: "Temple",
+            background: color_from_usize(222, 200, 160, 255),
+            slot: color_from_usize(240, 226, 196, 255),
+            tile: color_from_usize(205, 180, 135, 255),
+            line: color_from_usize(70, 50, 30, 255),
+            text: color_from_usize(50, 35, 20, 255),
+            exit: color_from_usize(40, 140, 60, 255),
+            teleport: color_from_usize(60, 90, 170, 255),
+            item: color_from_usize(150, 40, 30, 255),
+        }
+    }
+
+    /// Light lines on a dark background
+    pub fn night() -> Theme {
+        Theme {
+            name: "Night",
+            background: color_from_usize(25, 28, 36, 255),
+            slot: color_from_usize(45, 50, 62, 255),
+            tile: color_from_usize(60, 64, 78, 255),
+            line: color_from_usize(180, 185, 200, 255),
+            text: color_from_usize(230, 230, 235, 255),
+            exit: color_from_usize(90, 210, 120, 255),
+            teleport: color_from_usize(120, 160, 255, 255),
+            item: color_from_usize(255, 170, 80, 255),
+        }
+    }
+}
+
+/// Returns a box on the turn track for every turn of the map. The boxes are split into
+/// the difficulty's turn bands, with a gap between bands like the printed track.
+pub fn turn_boxes(turns: usize, difficulty: &Difficulty) -> Vec<Button> {
+    let (x, y, w, h) = TURN_TRACK;
+
+    // Each box takes a unit, with 0.4 units between boxes and a whole unit between bands
+    let gaps = difficulty.band(turns) - difficulty.band(1);
+    let unit = w / (turns as f32 * 1.4 - 0.4 + gaps as f32);
+    let size = h.min(unit * layout::ASPECT_RATIO);
+
+    let mut boxes = Vec::new();
+    let mut curr_x = x;
+    for turn in 1..=turns {
+        if turn > 1 && difficulty.band(turn) != difficulty.band(turn - 1) {
+            curr_x += unit;
+        }
+
+        boxes.push(Button::new(curr_x, y, unit, size));
+        curr_x += unit * 1.4;
+    }
+
+    boxes
+}
+
+/// Draw everything printed on the board for `game`, with the slots at the clickables
+pub fn draw(game: &GameState, clickables: &[Button], theme: &Theme) {
+    let (x, y, w, h) = layout::viewport();
+    draw_rectangle(x, y, w, h, theme.background);
+
+    // Slots that dice and items are placed in, labeled underneath
+    let slots = [
+        (ButtonId::Health,    "Health"),
+        (ButtonId::Die1,      "Die 1"),
+        (ButtonId::Die2,      "Die 2"),
+        (ButtonId::NextTile,  "Next Tile"),
+        (ButtonId::Encounter, "Encounter"),
+        (ButtonId::Charm,     "Charm"),
+        (ButtonId::Machete,   "Machete"),
+        (ButtonId::Pickaxe,   "Pickaxe"),
+        (ButtonId::Shotgun,   "Shotgun"),
+        (ButtonId::Bandage,   "Bandage"),
+        (ButtonId::Elixir,    "Elixir"),
+        (ButtonId::Idol,      "Idol"),
+    ];
+
+    for &(id, label) in slots.iter() {
+        let (x, y, w, h) = clickables[id as usize].to_screen();
+        draw_rectangle(x, y, w, h, theme.slot);
+        draw_rectangle_lines(x, y, w, h, 2., theme.line);
+
+        let size = (h * 0.3).max(10.);
+        text::draw_top(label, x, y + h + size * 0.2, size, theme.text);
+    }
+
+    // Steps of a turn, in the order they are played
+    let steps = [
+        (ButtonId::TurnAssignDice, "Assign dice"),
+        (ButtonId::TurnDrawWalls,  "Draw walls"),
+        (ButtonId::TurnMovement,   "Move"),
+        (ButtonId::TurnTileEffect, "Tile effect"),
+        (ButtonId::TurnEncounter,  "Encounter"),
+    ];
+
+    for &(id, label) in steps.iter() {
+        let (x, y, w, h) = clickables[id as usize].to_screen();
+        draw_rectangle(x, y, w, h, theme.slot);
+        text::draw_centered(label, (x, y, w, h), h * 0.7, theme.text);
+    }
+
+    for tile in 0..game.map.tiles() {
+        let rect = clickables[TILE_OFFSET + tile].to_screen();
+        draw_tile(&game.map, tile, rect, theme);
+    }
+
+    for (i, turn_box) in turn_boxes(game.map.turns, &game.difficulty).iter().enumerate() {
+        let (x, y, w, h) = turn_box.to_screen();
+        draw_rectangle(x, y, w, h, theme.slot);
+        draw_rectangle_lines(x, y, w, h, 2., theme.line);
+        text::draw_centered(&format!("{}", i + 1), (x, y, w, h), h * 0.7, theme.text);
+    }
+}
+
+/// Draw a single grid tile along with what the map puts on it
+fn draw_tile(map: &Map, tile: usize, rect: (f32, f32, f32, f32), theme: &Theme) {
+    let (x, y, w, h) = rect;
+    draw_rectangle(x, y, w, h, theme.tile);
+    draw_rectangle_lines(x, y, w, h, 2., theme.line);
+
+    let size = h / 5.;
+
+    if map.exits.contains(&tile) {
+        let inset = h * 0.08;
+        draw_rectangle_lines(x + inset, y + inset, w - inset * 2., h - inset * 2.,
+                             inset, theme.exit);
+        text::draw_top("Exit", x + w - size * 2.2, y + inset, size, theme.exit);
+    }
+
+    if tile == map.start {
+        text::draw_top("Start", x + h * 0.1, y + h * 0.1, size, theme.text);
+    }
+
+    // A cave is a dark ring in the middle of the tile
+    if map.teleports.contains(&tile) {
+        let (center_x, center_y) = (x + w / 2., y + h / 2.);
+        draw_circle(center_x, center_y, h * 0.25, theme.teleport);
+        draw_circle(center_x, center_y, h * 0.15, theme.tile);
+    }
+
+    if let Some(item) = map.item_at(tile) {
+        text::draw_top(&format!("{:?}", item), x + h * 0.1, y + h * 0.65, size,
+                       theme.item);
+    }
+}